@@ -1,28 +1,68 @@
-use miette::{IntoDiagnostic, Result, WrapErr};
+use serde::{Deserialize, Serialize};
 use std::env;
 
+/// Config is the settings shared by every component.
+///
+/// It is persisted by the settings repository, and the `TWITCH_*`
+/// environment variables are applied on top of it as an override layer.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
-    pub bot: String,
     pub channel: String,
+    /// client id of the twitch application used for the device code login.
+    pub client_id: String,
+    /// access token given by hand. when it is empty, the logged in user's token is used.
     pub token: String,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+const ENV_CHANNEL: &str = "TWITCH_CHANNEL";
+const ENV_CLIENT_ID: &str = "TWITCH_CLIENT_ID";
+const ENV_TOKEN: &str = "TWITCH_OAUTH_TOKEN";
+
 impl Config {
-    pub fn load_envs() -> Result<Self> {
-        let bot = env::var("TWITCH_BOT_USERNAME")
-            .into_diagnostic()
-            .wrap_err("TWITCH_BOT_USERNAME must be set.")?;
-        let channel = env::var("TWITCH_CHANNEL")
-            .into_diagnostic()
-            .wrap_err("TWITCH_CHANNEL must be set.")?;
-        let token = env::var("TWITCH_OAUTH_TOKEN")
-            .into_diagnostic()
-            .wrap_err("TWITCH_OAUTH_TOKEN must be set.")?;
+    /// overrides each field by its environment variable if it is set.
+    pub fn with_envs(mut self) -> Self {
+        let overrides: [(&str, &mut String); 3] = [
+            (ENV_CHANNEL, &mut self.channel),
+            (ENV_CLIENT_ID, &mut self.client_id),
+            (ENV_TOKEN, &mut self.token),
+        ];
+        for (key, field) in overrides {
+            if let Ok(value) = env::var(key) {
+                *field = value;
+            }
+        }
+        self
+    }
 
-        Ok(Self {
-            bot,
-            channel,
-            token,
-        })
+    pub fn validate(&self) -> Vec<ConfigError> {
+        let mut errors = vec![];
+        if self.channel.trim().is_empty() {
+            errors.push(ConfigError {
+                field: "channel",
+                message: format!("channel must be set (or overridden by {ENV_CHANNEL})."),
+            });
+        }
+        if self.client_id.is_empty() && self.token.is_empty() {
+            errors.push(ConfigError {
+                field: "client_id",
+                message: format!(
+                    "client_id (or {ENV_CLIENT_ID}) is required to login, unless a token is given by {ENV_TOKEN}."
+                ),
+            });
+        }
+        if self.channel.contains(char::is_whitespace) {
+            errors.push(ConfigError {
+                field: "channel",
+                message: "channel name must not contain whitespaces.".to_string(),
+            });
+        }
+        errors
     }
 }
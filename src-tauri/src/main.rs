@@ -13,20 +13,37 @@ use miette::{IntoDiagnostic, Result, WrapErr};
 use std::sync::Mutex;
 use tauri::{generate_context, generate_handler, Builder, Manager, SystemTray, WindowEvent};
 
-use presentation::tray;
+use model::error::MrDamianError;
+use presentation::{monitor, tray};
 use repository::Repositories;
 
 fn gen_bindings() {
     use presentation::command::*;
     tauri_specta::ts::export(
         specta::collect_types![
+            auth::auth_status,
+            auth::login,
+            auth::logout,
+            auth::missing_scopes,
             component::candidates,
             component::create_component,
+            component::update_params,
+            component::set_restart_policy,
+            component::inject_presets,
+            component::inject,
             editor::editor,
             editor::update_editor,
+            pipeline::health,
+            pipeline::duplicates,
+            log::logs,
             edge::add_edge,
             edge::remove_edge,
             edge::set_assignment,
+            edge::set_missing_policy,
+            edge::set_tap,
+            edge::traffic,
+            settings::settings,
+            settings::update_settings,
         ],
         "../src/bindings/index.ts",
     )
@@ -43,13 +60,29 @@ fn main() -> Result<()> {
 
     Builder::default()
         .invoke_handler(generate_handler![
+            auth::auth_status,
+            auth::login,
+            auth::logout,
+            auth::missing_scopes,
             component::candidates,
             component::create_component,
+            component::update_params,
+            component::set_restart_policy,
+            component::inject_presets,
+            component::inject,
             editor::editor,
             editor::update_editor,
+            pipeline::health,
+            pipeline::duplicates,
+            log::logs,
             edge::add_edge,
             edge::remove_edge,
             edge::set_assignment,
+            edge::set_missing_policy,
+            edge::set_tap,
+            edge::traffic,
+            settings::settings,
+            settings::update_settings,
         ])
         .system_tray(system_tray)
         .on_system_tray_event(tray::on_system_tray_event)
@@ -60,7 +93,16 @@ fn main() -> Result<()> {
             }
         })
         .setup(|app| {
-            app.manage(Mutex::new(Repositories::new()));
+            let data_dir = app
+                .path_resolver()
+                .app_data_dir()
+                .ok_or(MrDamianError::AppDataDirNotFound)?;
+            let mut repos = Repositories::new(&data_dir)?;
+            monitor::forward(app.handle(), &repos.monitor);
+            monitor::forward_logs(app.handle(), &repos.logs);
+            editor::restore(&app.handle(), &mut repos);
+            app.manage(Mutex::new(repos));
+            auth::check_scopes(app.handle());
 
             Ok(())
         })
@@ -12,10 +12,34 @@ pub enum MrDamianError {
     #[error("port not found")]
     PortNotFound(String),
 
-    #[error("invalid component")]
-    InvalidComponent,
+    #[error("{0} is not a known component kind")]
+    InvalidComponent(String),
+
+    #[error("not logged in to twitch")]
+    NotLoggedIn,
+
+    #[error("invalid template: {0}")]
+    InvalidTemplate(String),
+
+    #[error("template placeholder {{{0}}} has no value")]
+    PlaceholderNotFound(String),
+
+    #[error("invalid parameter {0}: {1}")]
+    InvalidParam(String, String),
+
+    #[error("subscription {0} was revoked: {1}")]
+    SubscriptionRevoked(String, String),
 
     // ------- infrastructure level errors
     #[error("window not found")]
     WindowNotFound,
+
+    #[error("app data directory not found")]
+    AppDataDirNotFound,
+}
+
+/// the error and its causes on one line, as the editor shows it.
+pub fn describe(err: &miette::Report) -> String {
+    let causes: Vec<String> = err.chain().map(ToString::to_string).collect();
+    causes.join(": ")
 }
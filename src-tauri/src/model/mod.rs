@@ -7,6 +7,101 @@ pub type Argument = String;
 pub type PropertyName = String;
 pub type Assignment = std::collections::HashMap<Argument, PropertyName>;
 pub type PropertyNames = Vec<PropertyName>;
+/// types of the properties by name. properties not listed here are treated as `Any`.
+pub type PropertyTypes = std::collections::HashMap<PropertyName, PropertyType>;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PropertyType {
+    #[default]
+    Any,
+    Text,
+    I64,
+    F64,
+    Bool,
+    Timestamp,
+    List,
+    Map,
+}
+
+impl PropertyType {
+    /// whether a property of type `self` can be assigned into an argument of type `arg`.
+    pub fn assignable_to(self, arg: PropertyType) -> bool {
+        self == arg || self == PropertyType::Any || arg == PropertyType::Any
+    }
+}
+/// per-node settings of a component, such as the text of a template.
+pub type Params = std::collections::HashMap<String, ParamValue>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamValue {
+    Text(String),
+    Integer(i64),
+}
+
+impl ParamValue {
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            ParamValue::Text(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            ParamValue::Integer(v) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamType {
+    Text,
+    Multiline,
+    Integer,
+    Choice(Vec<String>),
+}
+
+/// ParamSpec declares one per-node parameter that a component accepts.
+#[derive(Debug, Clone)]
+pub struct ParamSpec {
+    pub name: String,
+    pub label: String,
+    pub ty: ParamType,
+    pub default: ParamValue,
+}
+
+impl ParamSpec {
+    pub fn new(name: &str, label: &str, ty: ParamType, default: ParamValue) -> Self {
+        Self {
+            name: name.to_string(),
+            label: label.to_string(),
+            ty,
+            default,
+        }
+    }
+
+    /// checks the given value against the type, and returns the default if it is not given.
+    pub fn resolve(&self, value: Option<&ParamValue>) -> Result<ParamValue, error::MrDamianError> {
+        let Some(value) = value else {
+            return Ok(self.default.clone());
+        };
+        let invalid =
+            |reason: String| error::MrDamianError::InvalidParam(self.name.clone(), reason);
+        match (&self.ty, value) {
+            (ParamType::Text | ParamType::Multiline, ParamValue::Text(_))
+            | (ParamType::Integer, ParamValue::Integer(_)) => Ok(value.clone()),
+            (ParamType::Choice(options), ParamValue::Text(v)) => {
+                if options.contains(v) {
+                    Ok(value.clone())
+                } else {
+                    Err(invalid(format!("must be one of {}", options.join(", "))))
+                }
+            }
+            (ty, v) => Err(invalid(format!("{:?} is not a value of {:?}", v, ty))),
+        }
+    }
+}
 
 #[derive(Debug, Default, Clone)]
 pub struct Pipeline {
@@ -20,6 +115,19 @@ pub struct Component {
     pub id: String,
     pub outputs: Vec<OutputPort>,
     pub inputs: Vec<InputPort>,
+    pub params: Params,
+    pub restart: RestartPolicy,
+}
+
+/// RestartPolicy decides whether the supervisor restarts the process of a component
+/// after it stops.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RestartPolicy {
+    Never,
+    /// restart only when the process fails.
+    #[default]
+    OnFailure,
+    Always,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -28,6 +136,85 @@ pub struct Connection {
     pub source: InputPortID,
     pub target: OutputPortID,
     pub assignment: Assignment,
+    pub missing: MissingPolicy,
+}
+
+/// MissingPolicy decides what a connection does with a message
+/// which lacks a property used by its assignment.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MissingPolicy {
+    /// drop the message.
+    #[default]
+    Skip,
+    /// deliver the message with the zero value of the argument type.
+    FillDefault,
+    /// drop the message, and send the failure to the `error` port of the source component.
+    Error,
+}
+
+/// name of the output port which receives messages failed by `MissingPolicy::Error`.
+pub const ERROR_PORT: &str = "error";
+
+/// DroppedPacket records a message which was not delivered or handled as is.
+#[derive(Debug, Clone)]
+pub enum DroppedPacket {
+    /// a connection could not fill the argument from the message.
+    Missing {
+        connection: String,
+        argument: Argument,
+        property: PropertyName,
+        policy: MissingPolicy,
+    },
+    /// the process of the component discarded the message it received.
+    Discarded { component: String, reason: String },
+}
+
+/// ProcessState is the lifecycle of the process of a component.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessState {
+    #[default]
+    Initializing,
+    Running,
+    /// waiting for the backoff before the supervisor restarts the process.
+    Restarting,
+    /// stopped with an error, and is not restarted.
+    Failed,
+    /// stopped without an error, and is not restarted.
+    Stopped,
+}
+
+/// Health is the latest status of the process of a component.
+#[derive(Debug, Default, Clone)]
+pub struct Health {
+    pub component: String,
+    pub state: ProcessState,
+    /// the error the process stopped with last time, kept over restarts.
+    pub error: Option<String>,
+    pub restarts: u32,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    #[default]
+    Info,
+    Warn,
+    Error,
+}
+
+/// LogEntry is a log record of the backend.
+/// `component` and `kind` are taken from the span of the process which logged it.
+#[derive(Debug, Default, Clone)]
+pub struct LogEntry {
+    /// milliseconds since the unix epoch.
+    pub timestamp: u64,
+    pub level: LogLevel,
+    pub target: String,
+    pub component: Option<String>,
+    pub kind: Option<String>,
+    pub message: String,
+    pub fields: Vec<(String, String)>,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -40,6 +227,38 @@ pub struct InputPortID {
 pub struct InputPort {
     pub id: InputPortID,
     pub property_names: PropertyNames,
+    pub property_types: PropertyTypes,
+    /// properties the component works without. the others have to be assigned.
+    pub optional: PropertyNames,
+}
+
+impl InputPort {
+    pub fn new(parent: &str, name: &str, props: &[(&str, PropertyType)]) -> Self {
+        Self {
+            id: InputPortID {
+                parent: parent.to_string(),
+                name: name.to_string(),
+            },
+            property_names: props.iter().map(|(n, _)| n.to_string()).collect(),
+            property_types: props.iter().map(|(n, t)| (n.to_string(), *t)).collect(),
+            optional: vec![],
+        }
+    }
+
+    pub fn with_optional(mut self, names: &[&str]) -> Self {
+        self.optional = names.iter().map(|n| n.to_string()).collect();
+        self
+    }
+
+    pub fn property_type(&self, name: &str) -> PropertyType {
+        self.property_types.get(name).copied().unwrap_or_default()
+    }
+
+    pub fn required(&self) -> impl Iterator<Item = &PropertyName> {
+        self.property_names
+            .iter()
+            .filter(|n| !self.optional.contains(n))
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -51,12 +270,159 @@ pub struct OutputPortID {
 pub struct OutputPort {
     pub id: OutputPortID,
     pub property_names: PropertyNames,
+    pub property_types: PropertyTypes,
+}
+
+impl OutputPort {
+    pub fn new(parent: &str, name: &str, props: &[(&str, PropertyType)]) -> Self {
+        Self {
+            id: OutputPortID {
+                parent: parent.to_string(),
+                name: name.to_string(),
+            },
+            property_names: props.iter().map(|(n, _)| n.to_string()).collect(),
+            property_types: props.iter().map(|(n, t)| (n.to_string(), *t)).collect(),
+        }
+    }
+
+    pub fn property_type(&self, name: &str) -> PropertyType {
+        self.property_types.get(name).copied().unwrap_or_default()
+    }
+}
+
+/// AssignmentError is an entry of an assignment which cannot work at runtime.
+#[derive(Debug, Clone)]
+pub struct AssignmentError {
+    pub connection: String,
+    pub argument: Argument,
+    pub property: PropertyName,
+    pub message: String,
+}
+
+/// ids of the components which send messages to each component.
+pub type Upstreams = std::collections::HashMap<String, Vec<String>>;
+
+impl Pipeline {
+    pub fn upstreams(&self) -> Upstreams {
+        let mut res = Upstreams::new();
+        for conn in &self.connections {
+            let sources = res.entry(conn.target.parent.clone()).or_default();
+            if !sources.contains(&conn.source.parent) {
+                sources.push(conn.source.parent.clone());
+            }
+        }
+        res
+    }
+
+    /// checks every assignment against the property types of the connected ports.
+    pub fn assignment_errors(&self) -> Vec<AssignmentError> {
+        let mut res = vec![];
+        for conn in &self.connections {
+            let source = self
+                .components
+                .iter()
+                .find(|c| c.id == conn.source.parent)
+                .and_then(|c| c.outputs.iter().find(|p| p.id.name == conn.source.name));
+            let target = self
+                .components
+                .iter()
+                .find(|c| c.id == conn.target.parent)
+                .and_then(|c| c.inputs.iter().find(|p| p.id.name == conn.target.name));
+            let (Some(source), Some(target)) = (source, target) else {
+                continue;
+            };
+
+            let mut error = |argument: &str, property: &str, message: String| {
+                res.push(AssignmentError {
+                    connection: conn.id.clone(),
+                    argument: argument.to_string(),
+                    property: property.to_string(),
+                    message,
+                })
+            };
+            for (arg, prop) in &conn.assignment {
+                if !target.property_names.contains(arg) {
+                    error(
+                        arg,
+                        prop,
+                        format!("{} has no argument {}", target.id.name, arg),
+                    );
+                    continue;
+                }
+                if !source.property_names.contains(prop) {
+                    error(
+                        arg,
+                        prop,
+                        format!("{} has no property {}", source.id.name, prop),
+                    );
+                    continue;
+                }
+                let (from, to) = (source.property_type(prop), target.property_type(arg));
+                if !from.assignable_to(to) {
+                    error(
+                        arg,
+                        prop,
+                        format!("{} is {:?} but {} expects {:?}", prop, from, arg, to),
+                    );
+                }
+            }
+        }
+        res
+    }
 }
 
 #[derive(Debug, Default, Clone)]
 pub struct Candidate {
     pub kind: Kind,
     pub label: String,
+    pub params: Vec<ParamSpec>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct MissingScopes {
+    pub component: String,
+    pub kind: Kind,
+    pub scopes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// the pipeline is not deployed.
+    Error,
+    /// the pipeline is deployed, but may not work as expected.
+    Warning,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueKind {
+    InvalidSettings,
+    UnknownKind,
+    InvalidParams,
+    DuplicateId,
+    DanglingEdge,
+    UnknownPort,
+    UnassignedArgument,
+    IncompatibleAssignment,
+    Cycle,
+    Unconnected,
+}
+
+/// ValidationIssue is an entry of the report made before the pipeline is deployed.
+#[derive(Debug, Clone)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub kind: IssueKind,
+    pub component: Option<String>,
+    pub connection: Option<String>,
+    pub message: String,
 }
 
 pub const PIPELINE_UPDATED: &str = "pipeline-updated";
+pub const SETTINGS_UPDATED: &str = "settings-updated";
+pub const PIPELINE_REJECTED: &str = "pipeline-rejected";
+pub const AUTH_UPDATED: &str = "auth-updated";
+pub const AUTH_FAILED: &str = "auth-failed";
+pub const SCOPES_CHECKED: &str = "scopes-checked";
+pub const PACKET_DROPPED: &str = "packet-dropped";
+pub const HEALTH_UPDATED: &str = "health-updated";
+pub const LOG_APPENDED: &str = "log-appended";
@@ -1,12 +1,22 @@
 pub mod pipeline;
-mod twitch;
+pub mod template;
+pub mod twitch;
+mod validate;
 
 use hashbrown::HashMap;
 use miette::{IntoDiagnostic, Result};
+use tokio::sync::watch;
 
-use crate::model::error::MrDamianError;
-use crate::model::{Candidate, Kind, Pipeline};
-use crate::operation::pipeline::{Component, Connection, Constructor, Handles};
+use crate::model::error::{self, MrDamianError};
+use crate::model::{
+    Candidate, Health, Kind, MissingScopes, OutputPort, Params, Pipeline, ProcessState,
+    PropertyType, ERROR_PORT,
+};
+use crate::operation::pipeline::{
+    port::{Destinations, Link},
+    Component, Connection, Constructor, Context, Handle, Handles, MonitorEvent, Signal, Supervisor,
+    Wiring,
+};
 
 pub struct Factory(HashMap<&'static str, Constructor>);
 
@@ -19,64 +29,664 @@ impl Factory {
         Self(map)
     }
 
-    pub fn create_component(&self, kind: &Kind, id: &str) -> Result<Box<dyn Component + Send>> {
-        if let Some(c) = self.0.get(kind.0.as_str()) {
-            Ok((c.gen)(id, &crate::config::Config::load_envs()?))
-        } else {
-            Err(MrDamianError::InvalidComponent).into_diagnostic()
+    pub fn create_component(
+        &self,
+        kind: &Kind,
+        id: &str,
+        params: &Params,
+        ctx: &Context,
+    ) -> Result<Box<dyn Component + Send>> {
+        let Some(c) = self.0.get(kind.0.as_str()) else {
+            return Err(MrDamianError::InvalidComponent(kind.0.clone())).into_diagnostic();
+        };
+        let params = self.resolve_params(kind, params)?;
+        Ok((c.gen)(id, &params, ctx))
+    }
+
+    /// validates the given parameters by the schema of the component kind,
+    /// filling the defaults and dropping ones which are not declared.
+    pub fn resolve_params(&self, kind: &Kind, params: &Params) -> Result<Params> {
+        let Some(c) = self.0.get(kind.0.as_str()) else {
+            return Err(MrDamianError::InvalidComponent(kind.0.clone())).into_diagnostic();
+        };
+        let mut res = Params::new();
+        for spec in &c.params {
+            let value = spec.resolve(params.get(&spec.name)).into_diagnostic()?;
+            res.insert(spec.name.clone(), value);
         }
+        Ok(res)
     }
 
-    pub fn create_pipeline(&self, pipeline: &Pipeline) -> Handles {
+    /// returns the pipeline with the ports of the components the factory would create,
+    /// since the ports sent by the editor may be stale.
+    /// components which cannot be created have no ports.
+    pub fn with_ports(&self, pipeline: &Pipeline, ctx: &Context) -> Pipeline {
+        let mut res = pipeline.clone();
+        for mcomp in &mut res.components {
+            let comp = self.create_component(&mcomp.kind, &mcomp.id, &mcomp.params, ctx);
+            (mcomp.inputs, mcomp.outputs) = match comp {
+                Ok(comp) => (comp.inputs(), outputs(comp.as_ref())),
+                Err(_) => (vec![], vec![]),
+            };
+        }
+        res
+    }
+
+    /// starts all components of the pipeline.
+    pub fn create_pipeline(&self, pipeline: &Pipeline, ctx: &Context) -> Handles {
+        let mut handles = Handles::default();
+        self.update_pipeline(&mut handles, &Pipeline::default(), pipeline, ctx);
+        handles
+    }
+
+    /// applies the difference from `old` to `new` on the running processes.
+    /// only components which are added, changed or stopped are (re)started,
+    /// and the others keep running while their connections are rewired.
+    pub fn update_pipeline(
+        &self,
+        handles: &mut Handles,
+        old: &Pipeline,
+        new: &Pipeline,
+        ctx: &Context,
+    ) {
+        if new.components.is_empty() {
+            stop(handles.take(), old);
+            return;
+        }
+
+        let removed =
+            handles.remove_if(|id, handle| handle.is_finished() || self.changed(old, new, id));
+
+        handles.clear_failures();
         let mut processes = HashMap::new();
-        for mcomp in &pipeline.components {
-            if let Ok(ocomp) = self.create_component(&mcomp.kind, mcomp.id.as_str()) {
-                let conn = Connection::new();
-                let proc = ocomp.spawn();
-                processes.insert(mcomp.id.clone(), (conn, proc));
+        for mcomp in &new.components {
+            if handles.get(&mcomp.id).is_some() {
+                continue;
+            }
+            match self.create_component(&mcomp.kind, mcomp.id.as_str(), &mcomp.params, ctx) {
+                Ok(ocomp) => {
+                    let (signal, receiver) = watch::channel(Signal::Run);
+                    let conn = Connection::new(ctx.monitor.clone(), receiver.clone());
+                    let supervisor = Supervisor::new(ocomp, mcomp.restart, ctx.monitor.clone());
+                    processes.insert(mcomp.id.clone(), (conn, supervisor, signal, receiver));
+                }
+                Err(err) => {
+                    tracing::error!(
+                        component = %mcomp.id,
+                        kind = %mcomp.kind.0,
+                        "failed to create component: {}",
+                        err
+                    );
+                    let health = Health {
+                        component: mcomp.id.clone(),
+                        state: ProcessState::Failed,
+                        error: Some(error::describe(&err)),
+                        restarts: 0,
+                    };
+                    ctx.monitor.report(MonitorEvent::Health(health.clone()));
+                    handles.fail(health);
+                }
             }
         }
 
-        for conn in &pipeline.connections {
-            let res =
-                processes.get_many_mut([conn.source.parent.as_str(), conn.target.parent.as_str()]);
-            if let Some([source, target]) = res {
-                Connection::connect(
-                    &mut source.0,
-                    &mut target.0,
-                    conn.source.name.as_str(),
-                    conn.target.name.as_str(),
-                    &conn.assignment,
-                );
-            }
+        let mut wirings: HashMap<String, Wiring> = handles
+            .iter()
+            .map(|(id, h)| (id.clone(), h.wiring.clone()))
+            .collect();
+        for (id, (conn, ..)) in &processes {
+            wirings.insert(id.clone(), conn.wiring());
         }
 
-        let mut handles = Handles::default();
-        for (_, mut proc) in processes {
-            let handle = tauri::async_runtime::spawn(async move {
-                let mut inst = proc.1.await?;
-                inst.run(&mut proc.0).await
-            });
-            handles.push(handle);
+        let typed = self.with_ports(new, ctx);
+        let mut destinations: HashMap<&str, Destinations> = HashMap::new();
+        for conn in &new.connections {
+            let (Some(target), true) = (
+                wirings.get(&conn.target.parent),
+                wirings.contains_key(&conn.source.parent),
+            ) else {
+                continue;
+            };
+            let types = typed
+                .components
+                .iter()
+                .find(|c| c.id == conn.target.parent)
+                .and_then(|c| c.inputs.iter().find(|p| p.id.name == conn.target.name))
+                .map(|p| p.property_types.clone())
+                .unwrap_or_default();
+            let link = Link {
+                edge: conn.id.clone(),
+                assignment: conn.assignment.clone(),
+                missing: conn.missing,
+                types,
+            };
+            destinations
+                .entry(conn.source.parent.as_str())
+                .or_default()
+                .entry(conn.source.name.clone())
+                .or_default()
+                .push(target.accquire(&conn.target.name, &link));
         }
-        handles
+        for (id, wiring) in &wirings {
+            wiring.rewire(destinations.remove(id.as_str()).unwrap_or_default());
+        }
+        // the removed ones are drained after nothing is sent to them any more.
+        stop(removed, old);
+
+        for (id, (conn, supervisor, stop, receiver)) in processes {
+            let wiring = conn.wiring();
+            let health = supervisor.health();
+            let task = tauri::async_runtime::spawn(supervisor.run(conn, receiver));
+            handles.insert(
+                &id,
+                Handle {
+                    task,
+                    wiring,
+                    health,
+                    stop,
+                },
+            );
+        }
+    }
+
+    /// whether the running process of the component cannot be kept for the new pipeline.
+    /// the connected output ports are compared only for the components
+    /// which decide what to subscribe by them when they start.
+    fn changed(&self, old: &Pipeline, new: &Pipeline, id: &str) -> bool {
+        let (Some(o), Some(n)) = (
+            old.components.iter().find(|c| c.id == id),
+            new.components.iter().find(|c| c.id == id),
+        ) else {
+            return true;
+        };
+        let restart_on_outputs = self
+            .0
+            .get(n.kind.0.as_str())
+            .map_or(false, |c| c.restart_on_outputs);
+        o.kind.0 != n.kind.0
+            || o.params != n.params
+            || o.restart != n.restart
+            || (restart_on_outputs && connected_outputs(old, id) != connected_outputs(new, id))
+    }
+
+    /// all scopes that any component may need, which are requested on login.
+    pub fn scopes(&self) -> Vec<&'static str> {
+        let mut scopes: Vec<&'static str> = self
+            .0
+            .values()
+            .flat_map(|c| c.scopes.iter().copied())
+            .collect();
+        scopes.sort();
+        scopes.dedup();
+        scopes
+    }
+
+    /// reports components in the pipeline which cannot run with the `granted` scopes.
+    pub fn missing_scopes(&self, pipeline: &Pipeline, granted: &[String]) -> Vec<MissingScopes> {
+        let mut res = vec![];
+        for comp in &pipeline.components {
+            let Some(c) = self.0.get(comp.kind.0.as_str()) else {
+                continue;
+            };
+            let connected = |port: &str| {
+                pipeline
+                    .connections
+                    .iter()
+                    .any(|conn| conn.source.parent == comp.id && conn.source.name == port)
+            };
+            let required = c.scopes.iter().chain(
+                c.port_scopes
+                    .iter()
+                    .filter(|(port, _)| connected(port))
+                    .flat_map(|(_, s)| s.iter()),
+            );
+
+            let mut scopes: Vec<String> = required
+                .filter(|s| !granted.iter().any(|g| g == *s))
+                .map(|s| s.to_string())
+                .collect();
+            scopes.sort();
+            scopes.dedup();
+            if !scopes.is_empty() {
+                res.push(MissingScopes {
+                    component: comp.id.clone(),
+                    kind: comp.kind.clone(),
+                    scopes,
+                });
+            }
+        }
+        res
     }
 
     pub fn candidates(&self) -> Vec<Candidate> {
         let mut res = vec![];
         for (_, c) in &self.0 {
+            if c.deprecated {
+                continue;
+            }
             res.push(Candidate {
                 kind: Kind(c.kind.to_string()),
                 label: c.label.to_string(),
+                params: c.params.clone(),
             });
         }
         res
     }
 }
 
+/// output ports of the component shown in the editor.
+/// components with outputs get the `error` port too, which receives messages
+/// failed by connections with `MissingPolicy::Error`.
+pub fn outputs(comp: &dyn Component) -> Vec<OutputPort> {
+    let mut outputs = comp.outputs();
+    if !outputs.is_empty() {
+        outputs.push(OutputPort::new(
+            &comp.id(),
+            ERROR_PORT,
+            &[
+                ("edge", PropertyType::Text),
+                ("port", PropertyType::Text),
+                ("argument", PropertyType::Text),
+                ("property", PropertyType::Text),
+            ],
+        ));
+    }
+    outputs
+}
+
+/// shuts the processes of the pipeline down in the background.
+pub fn stop(handles: Handles, pipeline: &Pipeline) {
+    tauri::async_runtime::spawn(handles.shutdown(pipeline.upstreams()));
+}
+
+fn connected_outputs<'a>(pipeline: &'a Pipeline, id: &str) -> Vec<&'a str> {
+    let mut ports: Vec<&str> = pipeline
+        .connections
+        .iter()
+        .filter(|c| c.source.parent == id)
+        .map(|c| c.source.name.as_str())
+        .collect();
+    ports.sort();
+    ports.dedup();
+    ports
+}
+
 pub fn factory() -> Factory {
     Factory::new(vec![
+        template::TemplateComponent::constructor(),
+        twitch::AnnouncementComponent::constructor(),
+        twitch::ChatComponent::constructor(),
+        twitch::LookupChannelComponent::constructor(),
+        twitch::LookupUserComponent::constructor(),
         twitch::PublisherComponent::constructor(),
+        twitch::SendChatComponent::constructor(),
+        twitch::ShoutoutComponent::constructor(),
         twitch::SubscriberComponent::constructor(),
     ])
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::InputPort;
+    use crate::model::{Component as MComponent, Connection as MConnection};
+    use crate::model::{InputPortID, OutputPortID};
+    use crate::operation::pipeline::{DefaultProcess, Message, Packet, Process, ProcessInit};
+    use crate::operation::twitch::{EventSubHub, OAuth, TokenProvider};
+    use async_trait::async_trait;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::mpsc::{self, UnboundedSender};
+
+    /// a context with valid settings, whose token is never used in the tests.
+    pub fn context() -> Context {
+        let oauth = OAuth::new("http://127.0.0.1:9/oauth2/".parse().unwrap());
+        Context {
+            config: crate::config::Config {
+                channel: "damian".to_string(),
+                client_id: "cid".to_string(),
+                token: String::new(),
+            },
+            auth: TokenProvider::from_access_token(oauth, "token"),
+            monitor: Default::default(),
+            eventsub: EventSubHub::default(),
+        }
+    }
+
+    fn constructor(
+        kind: &'static str,
+        scopes: &'static [&'static str],
+        port_scopes: Vec<(&'static str, &'static [&'static str])>,
+    ) -> Constructor {
+        Constructor {
+            kind,
+            label: kind,
+            scopes,
+            port_scopes,
+            params: vec![],
+            deprecated: false,
+            restart_on_outputs: false,
+            gen: Box::new(|_, _, _| unreachable!("not created in the test")),
+        }
+    }
+
+    /// FakeComponent reports the packets which its process receives, with its id.
+    struct FakeComponent {
+        id: String,
+        kind: &'static str,
+        received: UnboundedSender<(String, Packet)>,
+    }
+
+    impl Component for FakeComponent {
+        fn id(&self) -> String {
+            self.id.clone()
+        }
+
+        fn kind(&self) -> &'static str {
+            self.kind
+        }
+
+        fn label(&self) -> &'static str {
+            self.kind
+        }
+
+        fn inputs(&self) -> Vec<InputPort> {
+            vec![InputPort::new(&self.id, "in", &[])]
+        }
+
+        fn outputs(&self) -> Vec<OutputPort> {
+            vec![
+                OutputPort::new(&self.id, "out", &[]),
+                OutputPort::new(&self.id, "other", &[]),
+            ]
+        }
+
+        fn spawn(&self) -> ProcessInit {
+            let process = FakeProcess {
+                id: self.id.clone(),
+                received: self.received.clone(),
+            };
+            Box::pin(async move { Ok(Box::new(process) as Box<dyn Process + Send>) })
+        }
+    }
+
+    struct FakeProcess {
+        id: String,
+        received: UnboundedSender<(String, Packet)>,
+    }
+
+    #[async_trait]
+    impl Process for FakeProcess {
+        async fn run(&mut self, conn: &mut Connection) -> Result<()> {
+            self.default_run(conn).await
+        }
+    }
+
+    #[async_trait]
+    impl DefaultProcess for FakeProcess {
+        async fn handler(&mut self, packet: Packet) -> Result<Vec<Packet>> {
+            let _ = self.received.send((self.id.clone(), packet));
+            Ok(vec![])
+        }
+    }
+
+    pub fn fake(
+        kind: &'static str,
+        restart_on_outputs: bool,
+        received: &UnboundedSender<(String, Packet)>,
+    ) -> Constructor {
+        let received = received.clone();
+        Constructor {
+            restart_on_outputs,
+            gen: Box::new(move |id, _, _| {
+                Box::new(FakeComponent {
+                    id: id.to_string(),
+                    kind,
+                    received: received.clone(),
+                })
+            }),
+            ..constructor(kind, &[], vec![])
+        }
+    }
+
+    /// the health of the process, which is shared only by the same run of the supervisor.
+    fn health(handles: &Handles, id: &str) -> Arc<std::sync::Mutex<crate::model::Health>> {
+        handles
+            .get(id)
+            .expect("the process is not running")
+            .health
+            .clone()
+    }
+
+    /// a pipeline of the (id, kind) components, and the (source, output port, target) edges.
+    pub fn pipeline(components: &[(&str, &str)], edges: &[(&str, &str, &str)]) -> Pipeline {
+        Pipeline {
+            components: components
+                .iter()
+                .map(|(id, kind)| MComponent {
+                    id: id.to_string(),
+                    kind: Kind(kind.to_string()),
+                    ..Default::default()
+                })
+                .collect(),
+            connections: edges
+                .iter()
+                .map(|(source, port, target)| MConnection {
+                    id: format!("{}-{}-{}", source, port, target),
+                    source: InputPortID {
+                        parent: source.to_string(),
+                        name: port.to_string(),
+                    },
+                    target: OutputPortID {
+                        parent: target.to_string(),
+                        name: "in".to_string(),
+                    },
+                    ..Default::default()
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_missing_scopes() {
+        const FOLLOWERS: &str = "moderator:read:followers";
+        const SHOUTOUTS: &str = "moderator:manage:shoutouts";
+        let factory = Factory::new(vec![
+            constructor(
+                "Subscriber",
+                &[],
+                vec![("follow", &[FOLLOWERS]), ("raid", &[])],
+            ),
+            constructor("Shoutout", &[SHOUTOUTS], vec![]),
+            constructor("Template", &[], vec![]),
+        ]);
+        let pipeline = pipeline(
+            &[
+                ("follows", "Subscriber"),
+                ("raids", "Subscriber"),
+                ("idle", "Subscriber"),
+                ("shoutout", "Shoutout"),
+                ("template", "Template"),
+            ],
+            &[
+                ("follows", "follow", "template"),
+                ("raids", "raid", "shoutout"),
+            ],
+        );
+
+        let missing: Vec<(String, Vec<String>)> = factory
+            .missing_scopes(&pipeline, &[])
+            .into_iter()
+            .map(|m| (m.component, m.scopes))
+            .collect();
+        assert_eq!(
+            missing,
+            vec![
+                ("follows".to_string(), vec![FOLLOWERS.to_string()]),
+                ("shoutout".to_string(), vec![SHOUTOUTS.to_string()]),
+            ]
+        );
+
+        let granted = [FOLLOWERS.to_string(), SHOUTOUTS.to_string()];
+        assert!(factory.missing_scopes(&pipeline, &granted).is_empty());
+    }
+
+    #[test]
+    fn test_deprecated_kind() {
+        // pipelines saved with it still load, but it is not offered anymore.
+        let factory = factory();
+        assert!(factory.0.contains_key("TwitchPublisher"));
+        assert!(!factory
+            .candidates()
+            .iter()
+            .any(|c| c.kind.0 == "TwitchPublisher"));
+    }
+
+    #[test]
+    fn test_with_ports() {
+        let mut pipeline = pipeline(
+            &[
+                ("template", "Template"),
+                ("announce", "TwitchAnnouncement"),
+                ("unknown", "Unknown"),
+            ],
+            &[("template", "text", "announce")],
+        );
+        pipeline.connections[0].target.name = "message".to_string();
+        pipeline.connections[0].assignment = [("text".to_string(), "text".to_string())]
+            .into_iter()
+            .collect();
+        // the editor claims ports which the components do not have.
+        pipeline.components[1].inputs = vec![crate::model::InputPort::new(
+            "announce",
+            "message",
+            &[("text", PropertyType::Bool)],
+        )];
+        pipeline.components[0].outputs = vec![OutputPort::new(
+            "template",
+            "text",
+            &[("text", PropertyType::Text)],
+        )];
+        pipeline.components[2].inputs = pipeline.components[1].inputs.clone();
+        assert_eq!(pipeline.assignment_errors().len(), 1);
+
+        let typed = factory().with_ports(&pipeline, &context());
+        assert!(typed.assignment_errors().is_empty());
+        let outputs: Vec<&str> = typed.components[0]
+            .outputs
+            .iter()
+            .map(|p| p.id.name.as_str())
+            .collect();
+        assert_eq!(outputs, vec!["text", ERROR_PORT]);
+        assert_eq!(
+            typed.components[1].inputs[0].property_type("text"),
+            PropertyType::Text
+        );
+        assert!(typed.components[2].inputs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_move_edge() {
+        let (received, mut packets) = mpsc::unbounded_channel();
+        let factory = Factory::new(vec![fake("Fake", false, &received)]);
+        let ctx = context();
+        let components = [("a", "Fake"), ("b", "Fake"), ("c", "Fake")];
+        let old = pipeline(&components, &[("a", "out", "b")]);
+        let mut handles = factory.create_pipeline(&old, &ctx);
+        let before: Vec<_> = components
+            .iter()
+            .map(|(id, _)| health(&handles, id))
+            .collect();
+
+        let new = pipeline(&components, &[("a", "out", "c")]);
+        factory.update_pipeline(&mut handles, &old, &new, &ctx);
+
+        // every process keeps running as is.
+        for ((id, _), before) in components.iter().zip(before) {
+            assert!(Arc::ptr_eq(&before, &health(&handles, id)), "{id}");
+            assert!(!handles.get(id).unwrap().is_finished(), "{id}");
+        }
+
+        // and the packets from a go to c instead of b.
+        let packet = Packet {
+            port: "out".to_string(),
+            message: Message::new(),
+        };
+        handles
+            .get("a")
+            .unwrap()
+            .wiring
+            .inject(packet)
+            .await
+            .unwrap();
+        let (id, packet) = tokio::time::timeout(Duration::from_secs(5), packets.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(id, "c");
+        assert_eq!(packet.port, "in");
+        assert!(packets.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_restart_on_outputs() {
+        let (received, _packets) = mpsc::unbounded_channel();
+        let factory = Factory::new(vec![
+            fake("Fake", false, &received),
+            fake("Subscriber", true, &received),
+        ]);
+        let ctx = context();
+        let components = [
+            ("fake", "Fake"),
+            ("subscriber", "Subscriber"),
+            ("t", "Fake"),
+        ];
+        let old = pipeline(
+            &components,
+            &[("fake", "out", "t"), ("subscriber", "out", "t")],
+        );
+        let mut handles = factory.create_pipeline(&old, &ctx);
+        let fake = health(&handles, "fake");
+        let subscriber = health(&handles, "subscriber");
+
+        let new = pipeline(
+            &components,
+            &[("fake", "other", "t"), ("subscriber", "other", "t")],
+        );
+        factory.update_pipeline(&mut handles, &old, &new, &ctx);
+
+        assert!(Arc::ptr_eq(&fake, &health(&handles, "fake")));
+        assert!(!Arc::ptr_eq(&subscriber, &health(&handles, "subscriber")));
+    }
+
+    #[tokio::test]
+    async fn test_failed_to_create() {
+        let (received, _packets) = mpsc::unbounded_channel();
+        let factory = Factory::new(vec![fake("Fake", false, &received)]);
+        let ctx = context();
+        let mut events = ctx.monitor.subscribe();
+        let old = pipeline(&[("a", "Fake"), ("b", "Unknown")], &[("a", "out", "b")]);
+        let mut handles = factory.create_pipeline(&old, &ctx);
+
+        let failed: Vec<Health> = handles
+            .health()
+            .into_iter()
+            .filter(|h| h.state == ProcessState::Failed)
+            .collect();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].component, "b");
+        assert!(failed[0].error.as_ref().unwrap().contains("Unknown"));
+        let reported = std::iter::from_fn(|| events.try_recv().ok()).any(|e| {
+            matches!(e, MonitorEvent::Health(h) if h.component == "b" && h.state == ProcessState::Failed)
+        });
+        assert!(reported);
+
+        // it is tried again on the next update.
+        let new = pipeline(&[("a", "Fake"), ("b", "Fake")], &[("a", "out", "b")]);
+        factory.update_pipeline(&mut handles, &old, &new, &ctx);
+        assert!(handles
+            .health()
+            .iter()
+            .all(|h| h.state != ProcessState::Failed));
+        assert!(handles.get("b").is_some());
+    }
+}
@@ -1,14 +1,35 @@
 use async_trait::async_trait;
 use miette::Result;
+use tracing::Instrument;
 
 use super::{Connection, Packet};
-use crate::model::{InputPort, OutputPort};
+use crate::model::{InputPort, OutputPort, ParamSpec, Params};
 
-pub type Generator = dyn Fn(&str, &crate::config::Config) -> Box<dyn Component + Send>;
+/// Context is what every component is generated with.
+pub struct Context {
+    pub config: crate::config::Config,
+    pub auth: crate::operation::twitch::TokenProvider,
+    pub monitor: super::Monitor,
+    /// the EventSub websocket shared by the subscriber-type components.
+    pub eventsub: crate::operation::twitch::EventSubHub,
+}
+
+pub type Generator = dyn Fn(&str, &Params, &Context) -> Box<dyn Component + Send>;
 
 pub struct Constructor {
     pub kind: &'static str,
     pub label: &'static str,
+    /// OAuth scopes that the generated component needs to run.
+    pub scopes: &'static [&'static str],
+    /// OAuth scopes that are needed only while the output port is connected.
+    pub port_scopes: Vec<(&'static str, &'static [&'static str])>,
+    /// per-node parameters, which are resolved before they are given to `gen`.
+    pub params: Vec<ParamSpec>,
+    /// kept only to run the pipelines saved before it was replaced, and not offered in the editor.
+    pub deprecated: bool,
+    /// the process decides what to do by the connected output ports when it starts,
+    /// so it is restarted when they change.
+    pub restart_on_outputs: bool,
     pub gen: Box<Generator>,
 }
 
@@ -25,6 +46,12 @@ pub trait Component {
 #[async_trait]
 pub trait Process {
     async fn run(&mut self, conn: &mut Connection) -> Result<()>;
+
+    /// cleans up what the process holds outside, e.g. subscriptions, when it is stopped.
+    /// this is called with a timeout, and the process is aborted anyway after that.
+    async fn shutdown(&mut self) -> Result<()> {
+        Ok(())
+    }
 }
 
 pub type ProcessInit =
@@ -37,7 +64,13 @@ pub trait DefaultProcess: Process {
             let Some(packet) = conn.receive().await else {
                 return Ok(());
             };
-            let packets = self.handler(packet).await?;
+            let span = tracing::debug_span!("packet", port = %packet.port);
+            let packets = async {
+                tracing::debug!("handling packet");
+                self.handler(packet).await
+            }
+            .instrument(span)
+            .await?;
 
             for packet in packets {
                 conn.send(packet).await?;
@@ -54,6 +87,7 @@ pub trait PassiveProcess: Process {
         loop {
             let packets = self.handler().await?;
             for packet in packets {
+                tracing::debug!(port = %packet.port, "emitting packet");
                 connection.send(packet).await?;
             }
         }
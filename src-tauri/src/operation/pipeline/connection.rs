@@ -1,8 +1,20 @@
 use miette::Result;
+use tokio::sync::{mpsc::Sender, watch};
 
+use super::monitor::Monitor;
 use super::packet::Packet;
-use super::port::{InputPort, OutputPort, OutputPorts};
-use crate::model::Assignment;
+use super::port::{Destinations, InputPort, Link, OutputPort, OutputPorts};
+
+/// Signal tells the process of a connection how to stop.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    #[default]
+    Run,
+    /// handle the queued packets, and stop when the queue is empty.
+    Drain,
+    /// stop right away.
+    Stop,
+}
 
 #[derive(Debug)]
 pub struct Connection {
@@ -11,10 +23,13 @@ pub struct Connection {
 }
 
 impl Connection {
-    pub fn new() -> Connection {
+    pub fn new(monitor: Monitor, signal: watch::Receiver<Signal>) -> Connection {
         Self {
-            input: InputPort::new(),
-            outputs: OutputPorts::default(),
+            input: InputPort::new(signal),
+            outputs: OutputPorts {
+                monitor,
+                ..Default::default()
+            },
         }
     }
 
@@ -26,21 +41,38 @@ impl Connection {
         self.outputs.send(packet).await
     }
 
-    pub fn connect(
-        src: &mut Connection,
-        dst: &mut Connection,
-        src_port: &str,
-        dst_port: &str,
-        assignment: &Assignment,
-    ) {
-        src.attach(src_port, dst.accquire(dst_port, assignment))
+    pub fn wiring(&self) -> Wiring {
+        Wiring {
+            input: self.input.base_sender.clone(),
+            outputs: self.outputs.clone(),
+        }
+    }
+}
+
+/// Wiring is the part of a connection kept by the pipeline,
+/// which stays valid while the process owns the connection.
+#[derive(Debug, Clone)]
+pub struct Wiring {
+    input: Sender<Packet>,
+    outputs: OutputPorts,
+}
+
+impl Wiring {
+    /// an output port which delivers messages to the `dest` input port of this connection.
+    pub fn accquire(&self, dest: &str, link: &Link) -> OutputPort {
+        OutputPort {
+            dest: dest.to_string(),
+            sender: self.input.clone(),
+            link: link.clone(),
+        }
     }
 
-    fn attach(&mut self, src: &str, port: OutputPort) {
-        self.outputs.attach(src, port);
+    pub fn rewire(&self, ports: Destinations) {
+        self.outputs.replace(ports);
     }
 
-    fn accquire(&mut self, port: &str, assigment: &Assignment) -> OutputPort {
-        self.input.accquire(port, assigment)
+    /// sends the packet from the output port as if the process emitted it.
+    pub async fn inject(&self, packet: Packet) -> Result<()> {
+        self.outputs.send(packet).await
     }
 }
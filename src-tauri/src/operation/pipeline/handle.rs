@@ -1,18 +1,243 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+use super::{Signal, Wiring, SHUTDOWN_TIMEOUT};
+use crate::model::{Health, Upstreams};
+
+/// time given to the supervisor over the shutdown timeout, before the task is aborted.
+const ABORT_GRACE: Duration = Duration::from_secs(1);
+/// how long the processes may take to drain their queues when they are stopped together.
+const DRAIN_DEADLINE: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Shutdown completes when the stopped processes are finished.
+pub type Shutdown = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Handle is a running process of a component.
+#[derive(Debug)]
+pub struct Handle {
+    pub task: tauri::async_runtime::JoinHandle<miette::Result<()>>,
+    pub wiring: Wiring,
+    pub health: Arc<Mutex<Health>>,
+    /// tells the supervisor how to stop the process.
+    pub stop: watch::Sender<Signal>,
+}
+
+impl Handle {
+    pub fn is_finished(&self) -> bool {
+        self.task.inner().is_finished()
+    }
+
+    pub fn health(&self) -> Health {
+        self.health.lock().expect("Failed to lock health").clone()
+    }
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        // let the process clean up, and abort it if it does not stop in time.
+        let _ = self.stop.send(Signal::Stop);
+        let task = self.task.inner().abort_handle();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(SHUTDOWN_TIMEOUT + ABORT_GRACE).await;
+            task.abort();
+        });
+    }
+}
+
+/// Handles are the running processes by the component id.
+/// removing a handle stops its process.
 #[derive(Debug, Default)]
 pub struct Handles {
-    handles: Vec<tauri::async_runtime::JoinHandle<miette::Result<()>>>,
+    handles: HashMap<String, Handle>,
+    /// the health of the components whose process could not be created.
+    failures: HashMap<String, Health>,
 }
 
 impl Handles {
-    pub fn push(&mut self, handle: tauri::async_runtime::JoinHandle<miette::Result<()>>) {
-        self.handles.push(handle);
+    pub fn insert(&mut self, id: &str, handle: Handle) {
+        self.handles.insert(id.to_string(), handle);
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Handle> {
+        self.handles.get(id)
+    }
+
+    /// records that the process of the component could not be created.
+    pub fn fail(&mut self, health: Health) {
+        self.failures.insert(health.component.clone(), health);
+    }
+
+    /// forgets the failures, before the components are created again.
+    pub fn clear_failures(&mut self) {
+        self.failures.clear();
+    }
+
+    /// the status of every component, including the ones which could not be created.
+    pub fn health(&self) -> Vec<Health> {
+        self.handles
+            .values()
+            .map(Handle::health)
+            .chain(self.failures.values().cloned())
+            .collect()
+    }
+
+    /// takes out the processes which satisfy `f`, to be shut down.
+    pub fn remove_if(&mut self, mut f: impl FnMut(&str, &Handle) -> bool) -> Handles {
+        let ids: Vec<String> = self
+            .handles
+            .iter()
+            .filter(|(id, h)| f(id, h))
+            .map(|(id, _)| id.clone())
+            .collect();
+        Handles {
+            handles: ids
+                .iter()
+                .filter_map(|id| self.handles.remove_entry(id))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// takes out all processes, to be shut down.
+    pub fn take(&mut self) -> Handles {
+        std::mem::take(self)
+    }
+
+    /// stops the processes in the order of the pipeline. the sources are stopped first,
+    /// and the others drain their queues after all of their upstream ones are stopped.
+    /// the processes left at the deadline are stopped right away.
+    pub async fn shutdown(self, upstreams: Upstreams) {
+        let handles = &self.handles;
+        // upstream ones which are not shut down here are already rewired away.
+        let stopped = |id: &String| handles.get(id).map_or(true, |h| h.is_finished());
+        let drains = handles.iter().map(|(id, handle)| {
+            let upstreams = upstreams.get(id).cloned().unwrap_or_default();
+            async move {
+                if upstreams.is_empty() {
+                    let _ = handle.stop.send(Signal::Stop);
+                } else {
+                    while !upstreams.iter().all(stopped) {
+                        tokio::time::sleep(POLL_INTERVAL).await;
+                    }
+                    let _ = handle.stop.send(Signal::Drain);
+                }
+                while !handle.is_finished() {
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+            }
+        });
+        let drained = futures::future::join_all(drains);
+        if tokio::time::timeout(DRAIN_DEADLINE, drained).await.is_err() {
+            tracing::warn!("processes did not drain within {:?}", DRAIN_DEADLINE);
+        }
+
+        for handle in handles.values() {
+            let _ = handle.stop.send(Signal::Stop);
+        }
+        let finished = async {
+            while !handles.values().all(|h| h.is_finished()) {
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        };
+        // the ones still running are aborted when they are dropped.
+        let _ = tokio::time::timeout(SHUTDOWN_TIMEOUT + ABORT_GRACE, finished).await;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Handle)> {
+        self.handles.iter()
     }
 }
 
-impl Drop for Handles {
-    fn drop(&mut self) {
-        for handle in self.handles.drain(..) {
-            handle.abort();
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::operation::pipeline::{Connection, Message, Monitor, Packet};
+
+    type Log = Arc<Mutex<Vec<String>>>;
+
+    /// a handle whose process logs the signals and then the queued packets.
+    /// a `stubborn` process ignores Drain, and only stops on Stop.
+    fn handle(id: &'static str, log: &Log, queued: usize, stubborn: bool) -> Handle {
+        let (stop, mut signal) = watch::channel(Signal::Run);
+        let mut conn = Connection::new(Monitor::default(), signal.clone());
+        for i in 0..queued {
+            let packet = Packet {
+                port: i.to_string(),
+                message: Message::default(),
+            };
+            conn.input.base_sender.try_send(packet).unwrap();
+        }
+        let wiring = conn.wiring();
+        let log = log.clone();
+        let task = tokio::spawn(async move {
+            while signal.changed().await.is_ok() {
+                let received = *signal.borrow();
+                log.lock().unwrap().push(format!("{id} {:?}", received));
+                if received == Signal::Stop || !stubborn {
+                    break;
+                }
+            }
+            while let Some(packet) = conn.receive().await {
+                log.lock().unwrap().push(format!("{id} {}", packet.port));
+            }
+            Ok(())
+        });
+        Handle {
+            task: tauri::async_runtime::JoinHandle::Tokio(task),
+            wiring,
+            health: Arc::default(),
+            stop,
+        }
+    }
+
+    fn handles(handles: Vec<(&str, Handle)>) -> Handles {
+        let mut res = Handles::default();
+        for (id, handle) in handles {
+            res.insert(id, handle);
         }
+        res
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_drain_in_order() {
+        let log = Log::default();
+        let running = handles(vec![
+            ("source", handle("source", &log, 0, false)),
+            ("sink", handle("sink", &log, 2, false)),
+        ]);
+        let upstreams = Upstreams::from([("sink".to_string(), vec!["source".to_string()])]);
+
+        let start = tokio::time::Instant::now();
+        running.shutdown(upstreams).await;
+        assert!(start.elapsed() < DRAIN_DEADLINE);
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["source Stop", "sink Drain", "sink 0", "sink 1"]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_drain_deadline() {
+        let log = Log::default();
+        let running = handles(vec![
+            ("source", handle("source", &log, 0, false)),
+            ("sink", handle("sink", &log, 1, true)),
+        ]);
+        let upstreams = Upstreams::from([("sink".to_string(), vec!["source".to_string()])]);
+
+        let start = tokio::time::Instant::now();
+        running.shutdown(upstreams).await;
+        assert!(start.elapsed() >= DRAIN_DEADLINE);
+        assert!(start.elapsed() < DRAIN_DEADLINE + SHUTDOWN_TIMEOUT);
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["source Stop", "sink Drain", "sink Stop", "sink 0"]
+        );
     }
 }
@@ -1,11 +1,175 @@
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::model::PropertyType;
+
 pub type Name = String;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
 pub enum Property {
     Text(String),
     I64(i64),
+    F64(f64),
+    Bool(bool),
+    /// RFC3339 formatted timestamp, as twitch gives.
+    Timestamp(String),
+    List(Vec<Property>),
+    Map(HashMap<Name, Property>),
 }
 
 pub type Message = HashMap<Name, Property>;
+
+impl Property {
+    /// the value filled for a missing property of the type.
+    pub fn zero(ty: PropertyType) -> Property {
+        match ty {
+            PropertyType::Any | PropertyType::Text => Property::Text(String::new()),
+            PropertyType::I64 => Property::I64(0),
+            PropertyType::F64 => Property::F64(0.0),
+            PropertyType::Bool => Property::Bool(false),
+            PropertyType::Timestamp => Property::Timestamp(String::new()),
+            PropertyType::List => Property::List(vec![]),
+            PropertyType::Map => Property::Map(HashMap::new()),
+        }
+    }
+
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            Property::Text(v) | Property::Timestamp(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Property::I64(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Property::Bool(v) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
+impl From<String> for Property {
+    fn from(v: String) -> Self {
+        Property::Text(v)
+    }
+}
+
+impl From<&str> for Property {
+    fn from(v: &str) -> Self {
+        Property::Text(v.to_string())
+    }
+}
+
+impl From<i64> for Property {
+    fn from(v: i64) -> Self {
+        Property::I64(v)
+    }
+}
+
+impl From<f64> for Property {
+    fn from(v: f64) -> Self {
+        Property::F64(v)
+    }
+}
+
+impl From<bool> for Property {
+    fn from(v: bool) -> Self {
+        Property::Bool(v)
+    }
+}
+
+impl<T: Into<Property>> From<Vec<T>> for Property {
+    fn from(v: Vec<T>) -> Self {
+        Property::List(v.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<HashMap<Name, Property>> for Property {
+    fn from(v: HashMap<Name, Property>) -> Self {
+        Property::Map(v)
+    }
+}
+
+impl std::fmt::Display for Property {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Property::Text(v) | Property::Timestamp(v) => write!(f, "{}", v),
+            Property::I64(v) => write!(f, "{}", v),
+            Property::F64(v) => write!(f, "{}", v),
+            Property::Bool(v) => write!(f, "{}", v),
+            Property::List(vs) => {
+                let items: Vec<String> = vs.iter().map(|v| v.to_string()).collect();
+                write!(f, "{}", items.join(", "))
+            }
+            Property::Map(vs) => {
+                // sort keys so that the rendered text is stable.
+                let mut items: Vec<String> =
+                    vs.iter().map(|(k, v)| format!("{}: {}", k, v)).collect();
+                items.sort();
+                write!(f, "{{{}}}", items.join(", "))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample() -> Property {
+        Property::Map(HashMap::from([
+            ("text".to_string(), Property::from("hello")),
+            ("viewers".to_string(), Property::from(42)),
+            ("ratio".to_string(), Property::from(0.5)),
+            ("mod".to_string(), Property::from(true)),
+            (
+                "at".to_string(),
+                Property::Timestamp("2023-05-01T12:00:00Z".to_string()),
+            ),
+            ("tags".to_string(), Property::from(vec!["a", "b"])),
+        ]))
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let prop = sample();
+        let json = serde_json::to_string(&prop).unwrap();
+        assert_eq!(serde_json::from_str::<Property>(&json).unwrap(), prop);
+    }
+
+    #[test]
+    fn test_serde_format() {
+        let json = serde_json::to_value(Property::from(42)).unwrap();
+        assert_eq!(json, serde_json::json!({"type": "i64", "value": 42}));
+        let json = serde_json::to_value(Property::from(vec![true])).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"type": "list", "value": [{"type": "bool", "value": true}]})
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Property::from("hello").to_string(), "hello");
+        assert_eq!(Property::from(42).to_string(), "42");
+        assert_eq!(Property::from(0.5).to_string(), "0.5");
+        assert_eq!(Property::from(false).to_string(), "false");
+        assert_eq!(
+            Property::Timestamp("2023-05-01T12:00:00Z".to_string()).to_string(),
+            "2023-05-01T12:00:00Z"
+        );
+        assert_eq!(Property::from(vec![1, 2]).to_string(), "1, 2");
+        assert_eq!(
+            sample().to_string(),
+            "{at: 2023-05-01T12:00:00Z, mod: true, ratio: 0.5, tags: a, b, text: hello, viewers: 42}"
+        );
+    }
+}
@@ -2,11 +2,17 @@ pub mod component;
 pub mod connection;
 pub mod handle;
 pub mod message;
+pub mod monitor;
 pub mod packet;
 pub mod port;
+pub mod supervisor;
+pub mod tap;
 
 pub use component::*;
 pub use connection::*;
 pub use handle::*;
 pub use message::*;
+pub use monitor::*;
 pub use packet::*;
+pub use supervisor::*;
+pub use tap::*;
@@ -0,0 +1,58 @@
+use tokio::sync::broadcast;
+
+use super::tap::Tap;
+use crate::model::{DroppedPacket, Health};
+
+/// MonitorEvent is what the running pipeline reports to the outside, e.g. the editor.
+#[derive(Debug, Clone)]
+pub enum MonitorEvent {
+    Dropped(DroppedPacket),
+    Health(Health),
+}
+
+/// Monitor is shared by every process of the pipeline to report events.
+/// Events are just discarded while nobody subscribes.
+#[derive(Debug, Clone)]
+pub struct Monitor {
+    sender: broadcast::Sender<MonitorEvent>,
+    tap: Tap,
+}
+
+impl Monitor {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(256);
+        Self {
+            sender,
+            tap: Tap::default(),
+        }
+    }
+
+    pub fn report(&self, event: MonitorEvent) {
+        // sending only fails when there is no subscriber, and the event is not needed then.
+        let _ = self.sender.send(event);
+    }
+
+    /// reports that the process of the component discarded a message, and logs why.
+    pub fn discard(&self, component: &str, reason: &str) {
+        tracing::info!(component, "discarded a message: {}", reason);
+        self.report(MonitorEvent::Dropped(DroppedPacket::Discarded {
+            component: component.to_string(),
+            reason: reason.to_string(),
+        }));
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<MonitorEvent> {
+        self.sender.subscribe()
+    }
+
+    /// the recorder of the packets delivered through each connection.
+    pub fn tap(&self) -> &Tap {
+        &self.tap
+    }
+}
+
+impl Default for Monitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
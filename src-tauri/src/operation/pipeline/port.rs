@@ -1,84 +1,353 @@
 use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
 use tokio::sync::mpsc::{channel, Receiver, Sender};
+use tokio::sync::watch;
 
 use miette::{IntoDiagnostic, Result};
 
-use super::message::Message;
+use super::connection::Signal;
+use super::message::{Message, Property};
+use super::monitor::{Monitor, MonitorEvent};
 use super::packet::Packet;
 use crate::model::error::MrDamianError;
-use crate::model::Assignment;
+use crate::model::{
+    Argument, Assignment, DroppedPacket, MissingPolicy, PropertyName, PropertyTypes, ERROR_PORT,
+};
+
+/// Link describes how messages are delivered through a connection.
+#[derive(Debug, Default, Clone)]
+pub struct Link {
+    pub edge: String,
+    pub assignment: Assignment,
+    pub missing: MissingPolicy,
+    /// types of the destination arguments, used to fill missing properties.
+    pub types: PropertyTypes,
+}
 
 #[derive(Debug)]
 pub struct InputPort {
     pub base_sender: Sender<Packet>,
     pub receiver: Receiver<Packet>,
+    pub signal: watch::Receiver<Signal>,
 }
 
 impl InputPort {
-    pub fn new() -> InputPort {
+    pub fn new(signal: watch::Receiver<Signal>) -> InputPort {
         let (base_sender, receiver) = channel::<Packet>(32);
         Self {
             base_sender,
             receiver,
+            signal,
         }
     }
 
-    pub fn accquire(&self, dest: &str, assignment: &Assignment) -> OutputPort {
-        OutputPort {
-            dest: dest.to_string(),
-            sender: self.base_sender.clone(),
-            assignment: assignment.clone(),
-        }
-    }
-
+    /// returns None when the queue is drained after the process is asked to stop.
     pub async fn receive(&mut self) -> Option<Packet> {
-        self.receiver.recv().await
+        loop {
+            // a dropped sender means the pipeline is gone, so it is drained as well.
+            let running = self.signal.has_changed().is_ok() && *self.signal.borrow() == Signal::Run;
+            if !running {
+                return self.receiver.try_recv().ok();
+            }
+            tokio::select! {
+                packet = self.receiver.recv() => return packet,
+                _ = self.signal.changed() => (),
+            }
+        }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct OutputPort {
     pub dest: String,
     pub sender: Sender<Packet>,
-    pub assignment: Assignment,
+    pub link: Link,
 }
 
 impl OutputPort {
-    pub async fn send(&self, message: Message) -> Result<()> {
+    /// picks the assigned properties from the message,
+    /// and returns the missing ones along with it.
+    fn assign(&self, message: &Message) -> (Message, Vec<(Argument, PropertyName)>) {
         let mut assigned = Message::default();
-        for (arg, prop) in self.assignment.iter() {
-            assigned.insert(arg.clone(), message[prop].clone());
+        let mut missing = vec![];
+        for (arg, prop) in self.link.assignment.iter() {
+            match message.get(prop) {
+                Some(value) => {
+                    assigned.insert(arg.clone(), value.clone());
+                }
+                None => {
+                    let ty = self.link.types.get(arg).copied().unwrap_or_default();
+                    assigned.insert(arg.clone(), Property::zero(ty));
+                    missing.push((arg.clone(), prop.clone()));
+                }
+            }
         }
+        (assigned, missing)
+    }
 
+    pub async fn send(&self, message: Message) -> Result<()> {
         let packet = Packet {
             port: self.dest.clone(),
-            message: assigned,
+            message,
         };
         self.sender.send(packet).await.into_diagnostic()
     }
 }
 
-#[derive(Debug, Default)]
+/// destinations of each output port.
+pub type Destinations = HashMap<String, Vec<OutputPort>>;
+
+/// OutputPorts is shared between the running process and the pipeline,
+/// so that the destinations can be rewired without restarting the process.
+#[derive(Debug, Default, Clone)]
 pub struct OutputPorts {
-    pub ports: HashMap<String, Vec<OutputPort>>,
+    pub ports: Arc<RwLock<Destinations>>,
+    pub monitor: Monitor,
 }
 
 impl OutputPorts {
-    pub fn attach(&mut self, src: &str, port: OutputPort) {
-        self.ports
-            .entry(src.to_string())
-            .or_insert(Vec::new())
-            .push(port);
+    /// replaces all destinations at once.
+    pub fn replace(&self, ports: Destinations) {
+        *self.ports.write().expect("Failed to lock output ports") = ports;
+    }
+
+    /// names of the ports which have at least one destination.
+    pub fn names(&self) -> Vec<String> {
+        self.destinations().keys().cloned().collect()
+    }
+
+    fn destinations(&self) -> std::sync::RwLockReadGuard<'_, Destinations> {
+        self.ports.read().expect("Failed to lock output ports")
+    }
+
+    /// takes a snapshot of the destinations of the port, not to hold the lock while sending.
+    fn port(&self, name: &str) -> Option<Vec<OutputPort>> {
+        self.destinations().get(name).cloned()
     }
 
     pub async fn send(&self, packet: Packet) -> Result<()> {
         let port = self
-            .ports
-            .get(&packet.port)
+            .port(&packet.port)
             .ok_or_else(|| MrDamianError::PortNotFound(packet.port.clone()))?;
-        for p in port {
-            p.send(packet.message.clone()).await?;
+        for p in &port {
+            let (assigned, missing) = p.assign(&packet.message);
+            for (argument, property) in &missing {
+                tracing::debug!(
+                    edge = %p.link.edge,
+                    argument = %argument,
+                    property = %property,
+                    "message lacks an assigned property"
+                );
+                if p.link.missing == MissingPolicy::FillDefault {
+                    // the message is still delivered, so nothing is dropped.
+                    continue;
+                }
+                self.monitor
+                    .report(MonitorEvent::Dropped(DroppedPacket::Missing {
+                        connection: p.link.edge.clone(),
+                        argument: argument.clone(),
+                        property: property.clone(),
+                        policy: p.link.missing,
+                    }));
+            }
+
+            match (missing.first(), p.link.missing) {
+                (None, _) | (Some(_), MissingPolicy::FillDefault) => {
+                    self.deliver(p, assigned).await?
+                }
+                (Some(_), MissingPolicy::Skip) => (),
+                (Some((argument, property)), MissingPolicy::Error) => {
+                    self.send_error(&packet, &p.link.edge, argument, property)
+                        .await?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// sends the failed message with the reason into the error port, if it is connected.
+    async fn send_error(
+        &self,
+        packet: &Packet,
+        edge: &str,
+        argument: &str,
+        property: &str,
+    ) -> Result<()> {
+        let Some(port) = self.port(ERROR_PORT) else {
+            return Ok(());
+        };
+
+        let mut message = packet.message.clone();
+        let reasons = [
+            ("edge", edge),
+            ("port", packet.port.as_str()),
+            ("argument", argument),
+            ("property", property),
+        ];
+        for (name, value) in reasons {
+            message.insert(name.to_string(), value.into());
+        }
+
+        for p in &port {
+            // the error port never fails over again, so skip what cannot be assigned.
+            let (assigned, missing) = p.assign(&message);
+            if missing.is_empty() {
+                self.deliver(p, assigned).await?;
+            }
         }
         Ok(())
     }
+
+    async fn deliver(&self, port: &OutputPort, message: Message) -> Result<()> {
+        self.monitor
+            .tap()
+            .record(&port.link.edge, &port.dest, &message);
+        port.send(message).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::PropertyType;
+
+    fn link(edge: &str, missing: MissingPolicy, assignment: &[(&str, &str)]) -> Link {
+        Link {
+            edge: edge.to_string(),
+            assignment: assignment
+                .iter()
+                .map(|(arg, prop)| (arg.to_string(), prop.to_string()))
+                .collect(),
+            missing,
+            types: [("count".to_string(), PropertyType::I64)].into(),
+        }
+    }
+
+    /// output ports whose `out` port goes through the link, and whose error port
+    /// passes the reasons with the text through.
+    fn ports(link: Link) -> (OutputPorts, Receiver<Packet>, Receiver<Packet>) {
+        let (sender, receiver) = channel(8);
+        let (error_sender, error_receiver) = channel(8);
+        let reasons = ["edge", "port", "argument", "property", "text"];
+        let ports = OutputPorts::default();
+        ports.replace(HashMap::from([
+            (
+                "out".to_string(),
+                vec![OutputPort {
+                    dest: "in".to_string(),
+                    sender,
+                    link,
+                }],
+            ),
+            (
+                ERROR_PORT.to_string(),
+                vec![OutputPort {
+                    dest: "errors".to_string(),
+                    sender: error_sender,
+                    link: Link {
+                        edge: "error-edge".to_string(),
+                        assignment: reasons
+                            .iter()
+                            .map(|r| (r.to_string(), r.to_string()))
+                            .collect(),
+                        ..Default::default()
+                    },
+                }],
+            ),
+        ]));
+        (ports, receiver, error_receiver)
+    }
+
+    fn packet() -> Packet {
+        Packet {
+            port: "out".to_string(),
+            message: Message::from([("text".to_string(), Property::from("hello"))]),
+        }
+    }
+
+    const ASSIGNMENT: &[(&str, &str)] = &[("body", "text"), ("count", "viewers")];
+
+    #[tokio::test]
+    async fn test_assigned() {
+        let link = link("edge", MissingPolicy::Skip, &[("body", "text")]);
+        let (ports, mut receiver, _errors) = ports(link);
+        let mut events = ports.monitor.subscribe();
+        ports.send(packet()).await.unwrap();
+
+        let packet = receiver.try_recv().unwrap();
+        assert_eq!(packet.port, "in");
+        assert_eq!(
+            packet.message,
+            Message::from([("body".to_string(), Property::from("hello"))])
+        );
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fill_default() {
+        let link = link("edge", MissingPolicy::FillDefault, ASSIGNMENT);
+        let (ports, mut receiver, mut errors) = ports(link);
+        let mut events = ports.monitor.subscribe();
+        ports.send(packet()).await.unwrap();
+
+        let packet = receiver.try_recv().unwrap();
+        assert_eq!(packet.message.get("body"), Some(&Property::from("hello")));
+        assert_eq!(packet.message.get("count"), Some(&Property::I64(0)));
+        assert!(errors.try_recv().is_err());
+        // the message is delivered, so it is not reported as dropped.
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_skip() {
+        let link = link("edge", MissingPolicy::Skip, ASSIGNMENT);
+        let (ports, mut receiver, mut errors) = ports(link);
+        let mut events = ports.monitor.subscribe();
+        ports.send(packet()).await.unwrap();
+
+        assert!(receiver.try_recv().is_err());
+        assert!(errors.try_recv().is_err());
+        let Ok(MonitorEvent::Dropped(DroppedPacket::Missing {
+            connection,
+            argument,
+            property,
+            policy,
+        })) = events.try_recv()
+        else {
+            panic!("the dropped packet is not reported");
+        };
+        assert_eq!(connection, "edge");
+        assert_eq!(argument, "count");
+        assert_eq!(property, "viewers");
+        assert_eq!(policy, MissingPolicy::Skip);
+    }
+
+    #[tokio::test]
+    async fn test_error() {
+        let link = link("edge", MissingPolicy::Error, ASSIGNMENT);
+        let (ports, mut receiver, mut errors) = ports(link);
+        let mut events = ports.monitor.subscribe();
+        ports.send(packet()).await.unwrap();
+
+        assert!(receiver.try_recv().is_err());
+        let packet = errors.try_recv().unwrap();
+        assert_eq!(packet.port, "errors");
+        let expected = [
+            ("edge", "edge"),
+            ("port", "out"),
+            ("argument", "count"),
+            ("property", "viewers"),
+            ("text", "hello"),
+        ];
+        for (name, value) in expected {
+            assert_eq!(packet.message.get(name), Some(&Property::from(value)));
+        }
+        assert!(matches!(
+            events.try_recv(),
+            Ok(MonitorEvent::Dropped(DroppedPacket::Missing {
+                policy: MissingPolicy::Error,
+                ..
+            }))
+        ));
+    }
 }
@@ -0,0 +1,354 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use miette::Result;
+use tokio::sync::watch;
+use tokio::time::Instant;
+use tracing::Instrument;
+
+use super::{Component, Connection, Monitor, MonitorEvent, Process, Signal};
+use crate::model::{error, Health, ProcessState, RestartPolicy};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+/// a process which ran longer than this is considered to have recovered,
+/// and the next restart starts over from the initial backoff.
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+/// how long a process may take to clean up when it is stopped.
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Supervisor runs the process of a component, and restarts it by the policy
+/// with exponential backoff. the connection is kept over restarts,
+/// so packets arriving in the meantime are processed by the next process.
+pub struct Supervisor {
+    component: Box<dyn Component + Send>,
+    policy: RestartPolicy,
+    status: Status,
+}
+
+/// Status keeps the health of the process, and reports every change of it.
+struct Status {
+    health: Arc<Mutex<Health>>,
+    monitor: Monitor,
+}
+
+impl Status {
+    fn update(&self, f: impl FnOnce(&mut Health)) -> Health {
+        let mut health = self.health.lock().expect("Failed to lock health");
+        f(&mut health);
+        self.monitor.report(MonitorEvent::Health(health.clone()));
+        health.clone()
+    }
+}
+
+impl Supervisor {
+    pub fn new(
+        component: Box<dyn Component + Send>,
+        policy: RestartPolicy,
+        monitor: Monitor,
+    ) -> Self {
+        let health = Health {
+            component: component.id(),
+            ..Default::default()
+        };
+        Self {
+            component,
+            policy,
+            status: Status {
+                health: Arc::new(Mutex::new(health)),
+                monitor,
+            },
+        }
+    }
+
+    /// the status of the process, which is updated while running.
+    pub fn health(&self) -> Arc<Mutex<Health>> {
+        self.status.health.clone()
+    }
+
+    /// runs the process in the span of the component, which the logs are tagged with.
+    /// the process is shut down by `signal`, or when the sender of it is dropped.
+    pub async fn run(self, conn: Connection, signal: watch::Receiver<Signal>) -> Result<()> {
+        let span = tracing::info_span!(
+            "process",
+            component = %self.component.id(),
+            kind = self.component.kind()
+        );
+        self.supervise(conn, signal).instrument(span).await
+    }
+
+    async fn supervise(
+        self,
+        mut conn: Connection,
+        mut signal: watch::Receiver<Signal>,
+    ) -> Result<()> {
+        let status = &self.status;
+        let mut backoff = INITIAL_BACKOFF;
+        loop {
+            let started = Instant::now();
+            status.update(|h| h.state = ProcessState::Initializing);
+            tracing::info!("initializing");
+            let init = self.component.spawn();
+            let inst = tokio::select! {
+                inst = init => inst,
+                _ = signal.changed() => return self.stop(),
+            };
+            let res = match inst {
+                Ok(mut inst) => {
+                    status.update(|h| h.state = ProcessState::Running);
+                    tracing::info!("running");
+                    let res = run_until_stopped(inst.as_mut(), &mut conn, &mut signal).await;
+                    match res {
+                        Some(res) if *signal.borrow() == Signal::Run => res,
+                        // stopped by the signal, or drained after it.
+                        res => {
+                            if let Some(Err(err)) = res {
+                                tracing::warn!("failed while draining: {}", err);
+                            }
+                            shutdown(inst.as_mut()).await;
+                            return self.stop();
+                        }
+                    }
+                }
+                Err(err) => Err(err),
+            };
+
+            let restart = match self.policy {
+                RestartPolicy::Never => false,
+                RestartPolicy::OnFailure => res.is_err(),
+                RestartPolicy::Always => true,
+            };
+            let health = status.update(|h| {
+                if let Err(err) = &res {
+                    h.error = Some(error::describe(err));
+                }
+                h.state = match (restart, &res) {
+                    (true, _) => ProcessState::Restarting,
+                    (false, Ok(_)) => ProcessState::Stopped,
+                    (false, Err(_)) => ProcessState::Failed,
+                };
+                if restart {
+                    h.restarts += 1;
+                }
+            });
+            match &res {
+                Ok(_) => tracing::info!("stopped"),
+                Err(err) => tracing::error!(error = ?err, "stopped: {}", err),
+            }
+            if !restart {
+                return res;
+            }
+
+            if started.elapsed() >= STABLE_AFTER {
+                backoff = INITIAL_BACKOFF;
+            }
+            tracing::warn!(restarts = health.restarts, "restarting in {:?}", backoff);
+            tokio::select! {
+                _ = tokio::time::sleep(backoff) => (),
+                _ = signal.changed() => return self.stop(),
+            }
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    fn stop(&self) -> Result<()> {
+        self.status.update(|h| h.state = ProcessState::Stopped);
+        tracing::info!("stopped by request");
+        Ok(())
+    }
+}
+
+/// runs the process until it returns, or returns None when it is stopped by `Signal::Stop`.
+/// on `Signal::Drain`, the process keeps running until its queue is drained.
+async fn run_until_stopped(
+    process: &mut (dyn Process + Send),
+    conn: &mut Connection,
+    signal: &mut watch::Receiver<Signal>,
+) -> Option<Result<()>> {
+    let run = process.run(conn);
+    tokio::pin!(run);
+    loop {
+        tokio::select! {
+            res = &mut run => return Some(res),
+            changed = signal.changed() => {
+                if changed.is_err() || *signal.borrow() == Signal::Stop {
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+async fn shutdown(process: &mut (dyn Process + Send)) {
+    tracing::info!("shutting down");
+    match tokio::time::timeout(SHUTDOWN_TIMEOUT, process.shutdown()).await {
+        Ok(Ok(())) => (),
+        Ok(Err(err)) => tracing::warn!("failed to shut down: {}", err),
+        Err(_) => tracing::warn!("shutdown timed out after {:?}", SHUTDOWN_TIMEOUT),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{InputPort, OutputPort};
+    use crate::operation::pipeline::ProcessInit;
+    use async_trait::async_trait;
+
+    /// FlakyComponent starts a process which stops after `lifetime`,
+    /// and records when it is started.
+    struct FlakyComponent {
+        lifetime: Duration,
+        fail: bool,
+        starts: Arc<Mutex<Vec<Instant>>>,
+    }
+
+    impl Component for FlakyComponent {
+        fn id(&self) -> String {
+            "flaky".to_string()
+        }
+
+        fn kind(&self) -> &'static str {
+            "Flaky"
+        }
+
+        fn label(&self) -> &'static str {
+            "Flaky"
+        }
+
+        fn inputs(&self) -> Vec<InputPort> {
+            vec![]
+        }
+
+        fn outputs(&self) -> Vec<OutputPort> {
+            vec![]
+        }
+
+        fn spawn(&self) -> ProcessInit {
+            self.starts.lock().unwrap().push(Instant::now());
+            let process = FlakyProcess {
+                lifetime: self.lifetime,
+                fail: self.fail,
+            };
+            Box::pin(async move { Ok(Box::new(process) as Box<dyn Process + Send>) })
+        }
+    }
+
+    struct FlakyProcess {
+        lifetime: Duration,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl Process for FlakyProcess {
+        async fn run(&mut self, _: &mut Connection) -> Result<()> {
+            tokio::time::sleep(self.lifetime).await;
+            if self.fail {
+                Err(miette::miette!("broken").wrap_err("failed"))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    struct Run {
+        /// the result, or None if it is still running after the given time.
+        res: Option<Result<()>>,
+        health: Health,
+        /// the time between the starts of the process.
+        intervals: Vec<Duration>,
+    }
+
+    /// supervises the flaky process for `duration` of the paused time.
+    async fn supervise(
+        policy: RestartPolicy,
+        lifetime: Duration,
+        fail: bool,
+        duration: Duration,
+    ) -> Run {
+        let starts = Arc::new(Mutex::new(vec![]));
+        let component = FlakyComponent {
+            lifetime,
+            fail,
+            starts: starts.clone(),
+        };
+        let supervisor = Supervisor::new(Box::new(component), policy, Monitor::new());
+        let health = supervisor.health();
+        let (_signal, receiver) = watch::channel(Signal::Run);
+        let conn = Connection::new(Monitor::new(), receiver.clone());
+        let res = tokio::time::timeout(duration, supervisor.run(conn, receiver))
+            .await
+            .ok();
+
+        let starts = starts.lock().unwrap();
+        let health = health.lock().unwrap().clone();
+        Run {
+            res,
+            health,
+            intervals: starts.windows(2).map(|w| w[1] - w[0]).collect(),
+        }
+    }
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[tokio::test(start_paused = true)]
+    async fn test_backoff() {
+        let run = supervise(RestartPolicy::OnFailure, Duration::ZERO, true, 20 * SECOND).await;
+        assert!(run.res.is_none());
+        assert_eq!(
+            run.intervals,
+            vec![SECOND, 2 * SECOND, 4 * SECOND, 8 * SECOND]
+        );
+        assert_eq!(run.health.restarts, 5);
+        assert_eq!(run.health.state, ProcessState::Restarting);
+        assert_eq!(run.health.error.as_deref(), Some("failed: broken"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_max_backoff() {
+        let run = supervise(
+            RestartPolicy::OnFailure,
+            Duration::ZERO,
+            true,
+            3600 * SECOND,
+        )
+        .await;
+        assert!(run.intervals.iter().all(|i| *i <= MAX_BACKOFF));
+        assert_eq!(run.intervals.last(), Some(&MAX_BACKOFF));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stable_reset() {
+        // processes which ran long enough are restarted after the initial backoff again.
+        let duration = 3 * (STABLE_AFTER + 2 * SECOND);
+        let run = supervise(RestartPolicy::OnFailure, STABLE_AFTER, true, duration).await;
+        assert_eq!(run.intervals, vec![STABLE_AFTER + INITIAL_BACKOFF; 3]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_never() {
+        let run = supervise(RestartPolicy::Never, Duration::ZERO, true, 60 * SECOND).await;
+        assert!(matches!(run.res, Some(Err(_))));
+        assert_eq!(run.health.state, ProcessState::Failed);
+        assert_eq!(run.health.restarts, 0);
+        assert!(run.intervals.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_on_failure() {
+        // a process which stops without an error is not restarted.
+        let run = supervise(RestartPolicy::OnFailure, SECOND, false, 60 * SECOND).await;
+        assert!(matches!(run.res, Some(Ok(()))));
+        assert_eq!(run.health.state, ProcessState::Stopped);
+        assert_eq!(run.health.restarts, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_always() {
+        let run = supervise(RestartPolicy::Always, Duration::ZERO, false, 10 * SECOND).await;
+        assert!(run.res.is_none());
+        assert_eq!(run.health.state, ProcessState::Restarting);
+        assert_eq!(run.intervals, vec![SECOND, 2 * SECOND, 4 * SECOND]);
+        assert_eq!(run.health.error, None);
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::message::Message;
+
+/// packets kept for each tapped connection.
+const CAPACITY: usize = 50;
+
+/// TappedPacket is a message delivered through a tapped connection.
+#[derive(Debug, Clone)]
+pub struct TappedPacket {
+    pub connection: String,
+    /// milliseconds since the unix epoch.
+    pub timestamp: u64,
+    /// the input port of the destination.
+    pub port: String,
+    /// the message after the assignment, as the destination received it.
+    pub message: Message,
+}
+
+/// Tap records the recent packets of the connections it is enabled for.
+/// it is off for every connection by default, not to copy every message.
+#[derive(Debug, Clone, Default)]
+pub struct Tap {
+    buffers: Arc<Mutex<HashMap<String, VecDeque<TappedPacket>>>>,
+}
+
+impl Tap {
+    pub fn enable(&self, connection: &str, enabled: bool) {
+        let mut buffers = self.buffers.lock().expect("Failed to lock tap");
+        if enabled {
+            buffers.entry(connection.to_string()).or_default();
+        } else {
+            buffers.remove(connection);
+        }
+    }
+
+    /// drops the recorded packets of the connections which are not in `connections`,
+    /// e.g. when the edges are removed.
+    pub fn retain(&self, connections: &[&str]) {
+        let mut buffers = self.buffers.lock().expect("Failed to lock tap");
+        buffers.retain(|id, _| connections.contains(&id.as_str()));
+    }
+
+    pub fn is_enabled(&self, connection: &str) -> bool {
+        let buffers = self.buffers.lock().expect("Failed to lock tap");
+        buffers.contains_key(connection)
+    }
+
+    /// recorded packets of the connection, the oldest first.
+    pub fn packets(&self, connection: &str) -> Vec<TappedPacket> {
+        let buffers = self.buffers.lock().expect("Failed to lock tap");
+        buffers
+            .get(connection)
+            .map(|b| b.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn record(&self, connection: &str, port: &str, message: &Message) {
+        let mut buffers = self.buffers.lock().expect("Failed to lock tap");
+        let Some(buffer) = buffers.get_mut(connection) else {
+            return;
+        };
+        if buffer.len() == CAPACITY {
+            buffer.pop_front();
+        }
+        buffer.push_back(TappedPacket {
+            connection: connection.to_string(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default(),
+            port: port.to_string(),
+            message: message.clone(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::operation::pipeline::Property;
+
+    fn message(i: i64) -> Message {
+        Message::from([("i".to_string(), Property::from(i))])
+    }
+
+    #[test]
+    fn test_keep_newest() {
+        let tap = Tap::default();
+        tap.enable("edge", true);
+        for i in 0..CAPACITY as i64 + 10 {
+            tap.record("edge", "in", &message(i));
+        }
+        let packets = tap.packets("edge");
+        assert_eq!(packets.len(), CAPACITY);
+        assert_eq!(packets[0].message, message(10));
+        assert_eq!(packets[CAPACITY - 1].message, message(CAPACITY as i64 + 9));
+    }
+
+    #[test]
+    fn test_disabled() {
+        let tap = Tap::default();
+        tap.record("edge", "in", &message(0));
+        assert!(!tap.is_enabled("edge"));
+        assert!(tap.packets("edge").is_empty());
+
+        tap.enable("edge", true);
+        tap.record("edge", "in", &message(1));
+        tap.enable("edge", false);
+        tap.record("edge", "in", &message(2));
+        assert!(tap.packets("edge").is_empty());
+    }
+
+    #[test]
+    fn test_retain() {
+        let tap = Tap::default();
+        tap.enable("kept", true);
+        tap.enable("removed", true);
+        tap.record("kept", "in", &message(0));
+        tap.record("removed", "in", &message(0));
+
+        tap.retain(&["kept"]);
+        assert_eq!(tap.packets("kept").len(), 1);
+        assert!(!tap.is_enabled("removed"));
+        assert!(tap.packets("removed").is_empty());
+    }
+}
@@ -0,0 +1,282 @@
+use async_trait::async_trait;
+
+use miette::{IntoDiagnostic, Result};
+
+use crate::{
+    model::error::MrDamianError,
+    model::{InputPort, OutputPort, ParamSpec, ParamType, ParamValue, Params, PropertyType},
+    operation::pipeline::{
+        Component, Connection, Constructor, Context, DefaultProcess, Message, Packet, Process,
+        ProcessInit, Property,
+    },
+};
+
+const KIND: &str = "Template";
+const LABEL: &str = "Template";
+
+/// name of the per-node parameter which holds the template text.
+pub const TEMPLATE_PARAM: &str = "template";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Placeholder(String),
+}
+
+/// Template is a text with `{name}` placeholders.
+/// `{{` and `}}` are written for literal braces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    pub fn parse(text: &str) -> std::result::Result<Self, MrDamianError> {
+        let mut segments = vec![];
+        let mut literal = String::new();
+        let mut chars = text.char_indices().peekable();
+
+        while let Some((pos, c)) = chars.next() {
+            match c {
+                '{' if matches!(chars.peek(), Some((_, '{'))) => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if matches!(chars.peek(), Some((_, '}'))) => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some((_, '}')) => break,
+                            Some((_, c)) if c.is_alphanumeric() || c == '_' => name.push(c),
+                            Some((_, c)) => {
+                                return Err(MrDamianError::InvalidTemplate(format!(
+                                    "unexpected '{}' in the placeholder at {}",
+                                    c, pos
+                                )))
+                            }
+                            None => {
+                                return Err(MrDamianError::InvalidTemplate(format!(
+                                    "placeholder at {} is not closed",
+                                    pos
+                                )))
+                            }
+                        }
+                    }
+                    if name.is_empty() {
+                        return Err(MrDamianError::InvalidTemplate(format!(
+                            "placeholder at {} has no name",
+                            pos
+                        )));
+                    }
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Placeholder(name));
+                }
+                '}' => {
+                    return Err(MrDamianError::InvalidTemplate(format!(
+                        "unmatched '}}' at {}",
+                        pos
+                    )))
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Self { segments })
+    }
+
+    /// placeholder names in order of their first appearance.
+    pub fn placeholders(&self) -> Vec<String> {
+        let mut names: Vec<String> = vec![];
+        for seg in &self.segments {
+            if let Segment::Placeholder(name) = seg {
+                if !names.contains(name) {
+                    names.push(name.clone());
+                }
+            }
+        }
+        names
+    }
+
+    pub fn render(&self, values: &Message) -> std::result::Result<String, MrDamianError> {
+        let mut res = String::new();
+        for seg in &self.segments {
+            match seg {
+                Segment::Literal(text) => res.push_str(text),
+                Segment::Placeholder(name) => {
+                    let value = values
+                        .get(name)
+                        .ok_or_else(|| MrDamianError::PlaceholderNotFound(name.clone()))?;
+                    res.push_str(&value.to_string());
+                }
+            }
+        }
+        Ok(res)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TemplateComponent {
+    id: String,
+    template: std::result::Result<Template, String>,
+}
+
+impl TemplateComponent {
+    pub fn constructor() -> Constructor {
+        Constructor {
+            kind: KIND,
+            label: LABEL,
+            scopes: &[],
+            port_scopes: vec![],
+            params: vec![ParamSpec::new(
+                TEMPLATE_PARAM,
+                "Template",
+                ParamType::Multiline,
+                ParamValue::Text(String::new()),
+            )],
+            deprecated: false,
+            restart_on_outputs: false,
+            gen: Box::new(
+                |id: &str, params: &Params, _: &Context| -> Box<dyn Component + Send> {
+                    let template = params
+                        .get(TEMPLATE_PARAM)
+                        .and_then(ParamValue::as_text)
+                        .unwrap_or_default();
+                    Box::new(TemplateComponent::new(id, template))
+                },
+            ),
+        }
+    }
+
+    pub fn new(id: &str, template: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            // keep the parse error to report it when the pipeline is deployed.
+            template: Template::parse(template).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+impl Component for TemplateComponent {
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn kind(&self) -> &'static str {
+        KIND
+    }
+
+    fn label(&self) -> &'static str {
+        LABEL
+    }
+
+    fn inputs(&self) -> Vec<InputPort> {
+        // placeholders are rendered by their Display, so any type is accepted.
+        let placeholders = self
+            .template
+            .as_ref()
+            .map(Template::placeholders)
+            .unwrap_or_default();
+        let props: Vec<(&str, PropertyType)> = placeholders
+            .iter()
+            .map(|p| (p.as_str(), PropertyType::Any))
+            .collect();
+        vec![InputPort::new(&self.id, "values", &props)]
+    }
+
+    fn outputs(&self) -> Vec<OutputPort> {
+        vec![OutputPort::new(
+            &self.id,
+            "text",
+            &[("text", PropertyType::Text)],
+        )]
+    }
+
+    fn spawn(&self) -> ProcessInit {
+        Box::pin(TemplateProcess::initializer(self.clone()))
+    }
+}
+
+pub struct TemplateProcess {
+    template: Template,
+}
+
+impl TemplateProcess {
+    pub async fn initializer(component: TemplateComponent) -> Result<Box<dyn Process + Send>> {
+        let template = component
+            .template
+            .map_err(MrDamianError::InvalidTemplate)
+            .into_diagnostic()?;
+        Ok(Box::new(Self { template }))
+    }
+}
+
+#[async_trait]
+impl Process for TemplateProcess {
+    async fn run(&mut self, conn: &mut Connection) -> Result<()> {
+        self.default_run(conn).await
+    }
+}
+
+#[async_trait]
+impl DefaultProcess for TemplateProcess {
+    async fn handler(&mut self, packet: Packet) -> Result<Vec<Packet>> {
+        if packet.port != "values" {
+            // drop all packets that are not from the values port.
+            return Ok(vec![]);
+        }
+
+        let text = self.template.render(&packet.message).into_diagnostic()?;
+        Ok(vec![Packet {
+            port: "text".to_string(),
+            message: Message::from([("text".to_string(), Property::Text(text))]),
+        }])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn render_placeholders() {
+        let template = Template::parse("{user}さんから{viewers}名のRAID {{{user}}}").unwrap();
+        assert_eq!(template.placeholders(), vec!["user", "viewers"]);
+
+        let values = Message::from([
+            ("user".to_string(), Property::Text("yuniruyuni".to_string())),
+            ("viewers".to_string(), Property::I64(10)),
+        ]);
+        assert_eq!(
+            template.render(&values).unwrap(),
+            "yuniruyuniさんから10名のRAID {yuniruyuni}"
+        );
+
+        let missing = Message::from([("user".to_string(), Property::I64(1))]);
+        assert!(matches!(
+            template.render(&missing),
+            Err(MrDamianError::PlaceholderNotFound(name)) if name == "viewers"
+        ));
+    }
+
+    #[test]
+    fn reject_broken_templates() {
+        for text in ["{user", "{}", "user}", "{user name}"] {
+            assert!(
+                matches!(
+                    Template::parse(text),
+                    Err(MrDamianError::InvalidTemplate(_))
+                ),
+                "{} should be rejected",
+                text
+            );
+        }
+    }
+}
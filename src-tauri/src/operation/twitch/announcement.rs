@@ -0,0 +1,166 @@
+use async_trait::async_trait;
+use twitch_api::{
+    helix::{chat::AnnouncementColor, HelixClient},
+    types::UserId,
+};
+
+use miette::{miette, IntoDiagnostic, Result};
+
+use crate::{
+    model::error::MrDamianError,
+    model::{InputPort, OutputPort, ParamSpec, ParamType, ParamValue, Params, PropertyType},
+    operation::pipeline::{
+        Component, Connection, Constructor, Context, DefaultProcess, Packet, Process, ProcessInit,
+        Property,
+    },
+    operation::twitch::{helix, TokenProvider},
+};
+
+const KIND: &str = "TwitchAnnouncement";
+const LABEL: &str = "Send Announcement";
+
+const COLORS: &[&str] = &["primary", "blue", "green", "orange", "purple"];
+
+#[derive(Debug, Clone)]
+pub struct AnnouncementComponent {
+    id: String,
+    auth: TokenProvider,
+    channel: String,
+    color: String,
+}
+
+impl AnnouncementComponent {
+    pub fn constructor() -> Constructor {
+        Constructor {
+            kind: KIND,
+            label: LABEL,
+            scopes: &["moderator:manage:announcements"],
+            port_scopes: vec![],
+            params: vec![ParamSpec::new(
+                "color",
+                "Color",
+                ParamType::Choice(COLORS.iter().map(|c| c.to_string()).collect()),
+                ParamValue::Text(COLORS[0].to_string()),
+            )],
+            deprecated: false,
+            restart_on_outputs: false,
+            gen: Box::new(
+                |id: &str, params: &Params, ctx: &Context| -> Box<dyn Component + Send> {
+                    let color = params
+                        .get("color")
+                        .and_then(ParamValue::as_text)
+                        .unwrap_or(COLORS[0]);
+                    Box::new(AnnouncementComponent::new(
+                        id,
+                        &ctx.config.channel,
+                        color,
+                        ctx.auth.clone(),
+                    ))
+                },
+            ),
+        }
+    }
+
+    pub fn new(id: &str, channel: &str, color: &str, auth: TokenProvider) -> Self {
+        Self {
+            id: id.to_string(),
+            channel: channel.to_string(),
+            color: color.to_string(),
+            auth,
+        }
+    }
+}
+
+impl Component for AnnouncementComponent {
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn kind(&self) -> &'static str {
+        KIND
+    }
+
+    fn label(&self) -> &'static str {
+        LABEL
+    }
+
+    fn inputs(&self) -> Vec<InputPort> {
+        vec![InputPort::new(
+            &self.id,
+            "message",
+            &[("text", PropertyType::Text)],
+        )]
+    }
+
+    fn outputs(&self) -> Vec<OutputPort> {
+        vec![]
+    }
+
+    fn spawn(&self) -> ProcessInit {
+        Box::pin(AnnouncementProcess::initializer(self.clone()))
+    }
+}
+
+pub struct AnnouncementProcess {
+    client: HelixClient<'static, reqwest::Client>,
+    auth: TokenProvider,
+    channel_id: UserId,
+    color: AnnouncementColor,
+}
+
+impl AnnouncementProcess {
+    pub async fn initializer(component: AnnouncementComponent) -> Result<Box<dyn Process + Send>> {
+        let client: HelixClient<reqwest::Client> = HelixClient::default();
+        let token = component.auth.token().await?;
+        let channel_id =
+            helix::get_user_id_for(&client, &token, &component.channel.as_str().into()).await?;
+        let color = AnnouncementColor::try_from(component.color.as_str())
+            .map_err(|_| miette!("unknown announcement color: {}", component.color))?;
+
+        Ok(Box::new(Self {
+            client,
+            auth: component.auth,
+            channel_id,
+            color,
+        }))
+    }
+
+    async fn announce(&self, text: &str) -> Result<()> {
+        let token = self.auth.token().await?;
+        self.client
+            .send_chat_announcement(
+                self.channel_id.as_str(),
+                token.user_id.as_str(),
+                text,
+                self.color.clone(),
+                &token,
+            )
+            .await
+            .into_diagnostic()?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Process for AnnouncementProcess {
+    async fn run(&mut self, conn: &mut Connection) -> Result<()> {
+        self.default_run(conn).await
+    }
+}
+
+#[async_trait]
+impl DefaultProcess for AnnouncementProcess {
+    async fn handler(&mut self, packet: Packet) -> Result<Vec<Packet>> {
+        if packet.port != "message" {
+            // drop all packets that are not from the message port.
+            return Ok(vec![]);
+        }
+
+        let msg = packet.message;
+        let Some(text) = msg.get("text").map(Property::to_string) else {
+            return Err(MrDamianError::MessageKeyNotFound.into());
+        };
+        self.announce(&text).await?;
+        Ok(vec![])
+    }
+}
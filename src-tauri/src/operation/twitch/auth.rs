@@ -0,0 +1,473 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use miette::{miette, IntoDiagnostic, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use twitch_api::twitch_oauth2::{Scope, UserToken};
+
+use crate::model::error::MrDamianError;
+use crate::repository::write_atomic;
+
+const DEFAULT_OAUTH2_URL: &str = "https://id.twitch.tv/oauth2/";
+
+// refresh tokens a bit before they really expire,
+// so that a request started just before expiry does not fail.
+const REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Credentials is what we keep about the logged in user.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Credentials {
+    pub client_id: String,
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub login: String,
+    pub user_id: String,
+    pub scopes: Vec<String>,
+    /// unix time in seconds, `None` means the token never expires.
+    pub expires_at: Option<u64>,
+}
+
+impl Credentials {
+    /// a token without a refresh token is used until it really expires,
+    /// since it cannot be refreshed ahead.
+    fn expires_soon(&self) -> bool {
+        let margin = match self.refresh_token {
+            Some(_) => REFRESH_MARGIN.as_secs(),
+            None => 0,
+        };
+        match self.expires_at {
+            Some(at) => at <= now() + margin,
+            None => false,
+        }
+    }
+
+    fn validated(&self) -> bool {
+        !self.user_id.is_empty()
+    }
+
+    fn user_token(&self) -> UserToken {
+        UserToken::from_existing_unchecked(
+            self.access_token.clone(),
+            self.refresh_token.clone().map(|t| t.into()),
+            self.client_id.clone(),
+            None,
+            self.login.clone().into(),
+            self.user_id.clone().into(),
+            Some(
+                self.scopes
+                    .iter()
+                    .map(|s| Scope::parse(s.clone()))
+                    .collect(),
+            ),
+            self.expires_at
+                .map(|at| Duration::from_secs(at.saturating_sub(now()))),
+        )
+    }
+}
+
+/// DeviceCode is shown to the user while the device code grant flow is waiting for approval.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceCode {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub expires_in: u64,
+    pub interval: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Grant {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_in: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Validated {
+    client_id: String,
+    login: String,
+    user_id: String,
+    #[serde(default)]
+    scopes: Vec<String>,
+    expires_in: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Failure {
+    #[serde(default)]
+    message: String,
+}
+
+/// OAuth talks with the twitch OAuth2 endpoints under `base`.
+#[derive(Debug, Clone)]
+pub struct OAuth {
+    base: url::Url,
+    http: reqwest::Client,
+}
+
+impl Default for OAuth {
+    fn default() -> Self {
+        Self::new(DEFAULT_OAUTH2_URL.parse().expect("valid default url"))
+    }
+}
+
+impl OAuth {
+    pub fn new(base: url::Url) -> Self {
+        Self {
+            base,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    fn endpoint(&self, path: &str) -> Result<url::Url> {
+        self.base.join(path).into_diagnostic()
+    }
+
+    /// starts the Device Code Grant flow.
+    pub async fn request_device_code(
+        &self,
+        client_id: &str,
+        scopes: &[&str],
+    ) -> Result<DeviceCode> {
+        let res = self
+            .http
+            .post(self.endpoint("device")?)
+            .form(&[("client_id", client_id), ("scopes", &scopes.join(" "))])
+            .send()
+            .await
+            .into_diagnostic()
+            .wrap_err("failed to request device code")?;
+        if !res.status().is_success() {
+            let failure: Failure = res.json().await.into_diagnostic()?;
+            return Err(miette!(
+                "device code request was rejected: {}",
+                failure.message
+            ));
+        }
+        res.json().await.into_diagnostic()
+    }
+
+    /// waits until the user approves (or denies) the device code, and returns the credentials.
+    pub async fn wait_device_approval(
+        &self,
+        client_id: &str,
+        scopes: &[&str],
+        code: &DeviceCode,
+    ) -> Result<Credentials> {
+        let mut interval = Duration::from_secs(code.interval.max(1));
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(code.expires_in);
+        loop {
+            if tokio::time::Instant::now() >= deadline {
+                return Err(miette!("device code expired before it was approved"));
+            }
+            tokio::time::sleep(interval).await;
+
+            let scopes = scopes.join(" ");
+            let res = self
+                .http
+                .post(self.endpoint("token")?)
+                .form(&[
+                    ("client_id", client_id),
+                    ("scopes", scopes.as_str()),
+                    ("device_code", code.device_code.as_str()),
+                    ("grant_type", DEVICE_CODE_GRANT),
+                ])
+                .send()
+                .await
+                .into_diagnostic()?;
+
+            if res.status().is_success() {
+                let grant: Grant = res.json().await.into_diagnostic()?;
+                return self.credentials(client_id, grant).await;
+            }
+
+            let failure: Failure = res.json().await.into_diagnostic()?;
+            match failure.message.as_str() {
+                "authorization_pending" => continue,
+                "slow_down" => interval += Duration::from_secs(5),
+                message => return Err(miette!("device code was not approved: {}", message)),
+            }
+        }
+    }
+
+    async fn refresh(&self, credentials: &Credentials) -> Result<Credentials> {
+        let Some(refresh_token) = credentials.refresh_token.as_deref() else {
+            return Err(miette!("token expired and there is no refresh token"));
+        };
+        let res = self
+            .http
+            .post(self.endpoint("token")?)
+            .form(&[
+                ("client_id", credentials.client_id.as_str()),
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token),
+            ])
+            .send()
+            .await
+            .into_diagnostic()
+            .wrap_err("failed to refresh token")?;
+        if !res.status().is_success() {
+            let failure: Failure = res.json().await.into_diagnostic()?;
+            return Err(miette!("token refresh was rejected: {}", failure.message));
+        }
+        let grant: Grant = res.json().await.into_diagnostic()?;
+        self.credentials(&credentials.client_id, grant).await
+    }
+
+    async fn validate(&self, access_token: &str) -> Result<Validated> {
+        let res = self
+            .http
+            .get(self.endpoint("validate")?)
+            .header("Authorization", format!("OAuth {access_token}"))
+            .send()
+            .await
+            .into_diagnostic()
+            .wrap_err("failed to validate token")?;
+        if !res.status().is_success() {
+            return Err(miette!("token is not valid (status {})", res.status()));
+        }
+        res.json().await.into_diagnostic()
+    }
+
+    async fn revalidate(&self, credentials: &mut Credentials) -> Result<()> {
+        let validated = self.validate(&credentials.access_token).await?;
+        credentials.client_id = validated.client_id;
+        credentials.login = validated.login;
+        credentials.user_id = validated.user_id;
+        credentials.scopes = validated.scopes;
+        credentials.expires_at = validated.expires_in.map(|e| now() + e);
+        Ok(())
+    }
+
+    async fn credentials(&self, client_id: &str, grant: Grant) -> Result<Credentials> {
+        let validated = self.validate(&grant.access_token).await?;
+        let client_id = if validated.client_id.is_empty() {
+            client_id.to_string()
+        } else {
+            validated.client_id
+        };
+        Ok(Credentials {
+            client_id,
+            access_token: grant.access_token,
+            refresh_token: grant.refresh_token,
+            login: validated.login,
+            user_id: validated.user_id,
+            scopes: validated.scopes,
+            expires_at: grant.expires_in.or(validated.expires_in).map(|e| now() + e),
+        })
+    }
+}
+
+/// TokenProvider hands out a user token to every component, refreshing it transparently.
+///
+/// Cloned providers share the same credentials,
+/// so a refresh done by one component is visible to the others.
+#[derive(Debug, Clone)]
+pub struct TokenProvider {
+    oauth: OAuth,
+    credentials: Arc<Mutex<Option<Credentials>>>,
+    store: Option<PathBuf>,
+}
+
+impl TokenProvider {
+    /// loads the credentials saved in `store`, if any.
+    pub fn load(oauth: OAuth, store: PathBuf) -> Result<Self> {
+        let credentials = if store.exists() {
+            let content = std::fs::read_to_string(&store).into_diagnostic()?;
+            Some(serde_json::from_str(&content).into_diagnostic()?)
+        } else {
+            None
+        };
+        Ok(Self {
+            oauth,
+            credentials: Arc::new(Mutex::new(credentials)),
+            store: Some(store),
+        })
+    }
+
+    /// a provider for a token which was given by hand, it is never stored nor refreshed.
+    pub fn from_access_token(oauth: OAuth, access_token: &str) -> Self {
+        Self {
+            oauth,
+            credentials: Arc::new(Mutex::new(Some(Credentials {
+                access_token: access_token.to_string(),
+                ..Default::default()
+            }))),
+            store: None,
+        }
+    }
+
+    pub fn oauth(&self) -> &OAuth {
+        &self.oauth
+    }
+
+    pub async fn credentials(&self) -> Option<Credentials> {
+        self.credentials.lock().await.clone()
+    }
+
+    pub async fn login(&self, credentials: Credentials) -> Result<()> {
+        let mut current = self.credentials.lock().await;
+        self.save(Some(&credentials))?;
+        *current = Some(credentials);
+        Ok(())
+    }
+
+    pub async fn logout(&self) -> Result<()> {
+        let mut current = self.credentials.lock().await;
+        self.save(None)?;
+        *current = None;
+        Ok(())
+    }
+
+    /// asks twitch about the current token, and returns the scopes which are really granted.
+    pub async fn validate(&self) -> Result<Vec<String>> {
+        let mut current = self.credentials.lock().await;
+        let Some(credentials) = current.as_mut() else {
+            return Err(MrDamianError::NotLoggedIn).into_diagnostic();
+        };
+        if credentials.expires_soon() {
+            *credentials = self.oauth.refresh(credentials).await?;
+            self.save(Some(credentials))?;
+        } else {
+            self.oauth.revalidate(credentials).await?;
+        }
+        Ok(credentials.scopes.clone())
+    }
+
+    pub async fn token(&self) -> Result<UserToken> {
+        let mut current = self.credentials.lock().await;
+        let Some(credentials) = current.as_mut() else {
+            return Err(MrDamianError::NotLoggedIn).into_diagnostic();
+        };
+
+        if !credentials.validated() {
+            self.oauth.revalidate(credentials).await?;
+        }
+
+        if credentials.expires_soon() {
+            *credentials = self.oauth.refresh(credentials).await?;
+            self.save(Some(credentials))?;
+        }
+
+        Ok(credentials.user_token())
+    }
+
+    fn save(&self, credentials: Option<&Credentials>) -> Result<()> {
+        let Some(path) = &self.store else {
+            return Ok(());
+        };
+        match credentials {
+            Some(credentials) => {
+                write_atomic(path, credentials).wrap_err("failed to save credentials")
+            }
+            None if path.exists() => std::fs::remove_file(path).into_diagnostic(),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::operation::twitch::test_util::{self, VALIDATED};
+
+    /// serves the responses in order for the OAuth2 endpoints, standing in for id.twitch.tv.
+    async fn stand_in(routes: Vec<(&'static str, u16, &'static str)>) -> url::Url {
+        let routes = std::sync::Mutex::new(routes.into_iter());
+        let (base, _) = test_util::stand_in(move |request| {
+            let (path, status, body) = routes.lock().unwrap().next().expect("unexpected request");
+            assert!(
+                request.line().starts_with(path),
+                "expected {path} but {request:?}"
+            );
+            (status, body.to_string())
+        })
+        .await;
+        base.join("oauth2/").unwrap()
+    }
+
+    const SCOPES: &[&str] = &["moderator:manage:shoutouts"];
+
+    #[tokio::test]
+    async fn test_device_code_flow() {
+        let base = stand_in(vec![
+            ("POST /oauth2/device", 200, r#"{"device_code":"dc","user_code":"ABCD","verification_uri":"https://www.twitch.tv/activate","expires_in":1800,"interval":1}"#),
+            ("POST /oauth2/token", 400, r#"{"status":400,"message":"authorization_pending"}"#),
+            ("POST /oauth2/token", 200, r#"{"access_token":"at","refresh_token":"rt","expires_in":14400,"scope":[],"token_type":"bearer"}"#),
+            ("GET /oauth2/validate", 200, VALIDATED),
+        ])
+        .await;
+        let oauth = OAuth::new(base);
+
+        let code = oauth.request_device_code("cid", SCOPES).await.unwrap();
+        assert_eq!(code.user_code, "ABCD");
+
+        let credentials = oauth
+            .wait_device_approval("cid", SCOPES, &code)
+            .await
+            .unwrap();
+        assert_eq!(credentials.access_token, "at");
+        assert_eq!(credentials.refresh_token.as_deref(), Some("rt"));
+        assert_eq!(credentials.login, "damian");
+    }
+
+    #[tokio::test]
+    async fn test_refresh_before_expiry() {
+        let base = stand_in(vec![
+            ("POST /oauth2/token", 200, r#"{"access_token":"new","refresh_token":"rt2","expires_in":14400,"scope":[],"token_type":"bearer"}"#),
+            ("GET /oauth2/validate", 200, VALIDATED),
+        ])
+        .await;
+        let provider = TokenProvider::from_access_token(OAuth::new(base), "old");
+        provider
+            .login(Credentials {
+                client_id: "cid".to_string(),
+                access_token: "old".to_string(),
+                refresh_token: Some("rt".to_string()),
+                login: "damian".to_string(),
+                user_id: "42".to_string(),
+                scopes: vec![],
+                expires_at: Some(now() + 10),
+            })
+            .await
+            .unwrap();
+
+        let token = provider.token().await.unwrap();
+        assert_eq!(token.access_token.as_str(), "new");
+        let credentials = provider.credentials().await.unwrap();
+        assert_eq!(credentials.refresh_token.as_deref(), Some("rt2"));
+    }
+
+    #[tokio::test]
+    async fn test_keep_token_without_refresh_token() {
+        // no request is expected.
+        let base = stand_in(vec![]).await;
+        let provider = TokenProvider::from_access_token(OAuth::new(base), "pasted");
+        provider
+            .login(Credentials {
+                client_id: "cid".to_string(),
+                access_token: "pasted".to_string(),
+                refresh_token: None,
+                login: "damian".to_string(),
+                user_id: "42".to_string(),
+                scopes: vec![],
+                expires_at: Some(now() + 10),
+            })
+            .await
+            .unwrap();
+
+        let token = provider.token().await.unwrap();
+        assert_eq!(token.access_token.as_str(), "pasted");
+    }
+}
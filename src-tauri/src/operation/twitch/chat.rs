@@ -0,0 +1,410 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use twitch_api::{helix::HelixClient, types::UserName};
+
+use miette::{IntoDiagnostic, Result};
+
+use crate::model::{InputPort, OutputPort, Params, PropertyType};
+use crate::operation::pipeline::{
+    Component, Connection, Constructor, Context, Message, Packet, PassiveProcess, Process,
+    ProcessInit, Property,
+};
+use crate::operation::twitch::{
+    helix, EventSubHub, SessionEvent, Subscription, Subscriptions, TokenProvider,
+};
+
+const KIND: &str = "TwitchChat";
+const LABEL: &str = "Twitch Chat";
+
+// channel.chat.message is not supported by twitch_api yet,
+// so the subscription and the notification are handled by hand.
+const CHAT_MESSAGE: &str = "channel.chat.message";
+
+const PROPERTIES: &[(&str, PropertyType)] = &[
+    ("broadcaster_user_id", PropertyType::Text),
+    ("broadcaster_user_login", PropertyType::Text),
+    ("broadcaster_user_name", PropertyType::Text),
+    ("user_id", PropertyType::Text),
+    ("user_login", PropertyType::Text),
+    ("user_name", PropertyType::Text),
+    ("badges", PropertyType::List),
+    ("message", PropertyType::Text),
+    ("fragments", PropertyType::List),
+    ("message_id", PropertyType::Text),
+    ("reply_parent_message_id", PropertyType::Text),
+    ("reply_parent_user_id", PropertyType::Text),
+    ("reply_parent_user_login", PropertyType::Text),
+    ("reply_parent_user_name", PropertyType::Text),
+    ("reply_parent_message_body", PropertyType::Text),
+];
+
+#[derive(Debug, Clone)]
+pub struct ChatComponent {
+    id: String,
+    auth: TokenProvider,
+    channel: UserName,
+    hub: EventSubHub,
+}
+
+impl ChatComponent {
+    pub fn constructor() -> Constructor {
+        Constructor {
+            kind: KIND,
+            label: LABEL,
+            scopes: &["user:read:chat"],
+            port_scopes: vec![],
+            params: vec![],
+            deprecated: false,
+            restart_on_outputs: false,
+            gen: Box::new(
+                |id: &str, _: &Params, ctx: &Context| -> Box<dyn Component + Send> {
+                    Box::new(ChatComponent::new(
+                        id,
+                        &ctx.config.channel,
+                        ctx.auth.clone(),
+                        ctx.eventsub.clone(),
+                    ))
+                },
+            ),
+        }
+    }
+
+    pub fn new(id: &str, channel: &str, auth: TokenProvider, hub: EventSubHub) -> Self {
+        Self {
+            id: id.to_string(),
+            channel: channel.into(),
+            auth,
+            hub,
+        }
+    }
+}
+
+impl Component for ChatComponent {
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn kind(&self) -> &'static str {
+        KIND
+    }
+
+    fn label(&self) -> &'static str {
+        LABEL
+    }
+
+    fn inputs(&self) -> Vec<InputPort> {
+        vec![]
+    }
+
+    fn outputs(&self) -> Vec<OutputPort> {
+        vec![OutputPort::new(&self.id, "message", PROPERTIES)]
+    }
+
+    fn spawn(&self) -> ProcessInit {
+        Box::pin(ChatProcess::initializer(self.clone()))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Frame {
+    payload: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatPayload {
+    event: ChatMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatMessage {
+    broadcaster_user_id: String,
+    broadcaster_user_login: String,
+    broadcaster_user_name: String,
+    chatter_user_id: String,
+    chatter_user_login: String,
+    chatter_user_name: String,
+    message_id: String,
+    message: ChatText,
+    #[serde(default)]
+    badges: Vec<Badge>,
+    #[serde(default)]
+    reply: Option<Reply>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatText {
+    text: String,
+    #[serde(default)]
+    fragments: Vec<Fragment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Fragment {
+    #[serde(rename = "type")]
+    kind: String,
+    text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Badge {
+    set_id: String,
+    id: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Reply {
+    parent_message_id: String,
+    parent_message_body: String,
+    parent_user_id: String,
+    parent_user_login: String,
+    parent_user_name: String,
+}
+
+impl From<ChatMessage> for Message {
+    fn from(msg: ChatMessage) -> Message {
+        let reply = msg.reply.unwrap_or_default();
+        let entry = |pairs: [(&str, String); 2]| -> Property {
+            Property::Map(
+                pairs
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), Property::Text(v)))
+                    .collect(),
+            )
+        };
+        let badges: Vec<Property> = msg
+            .badges
+            .into_iter()
+            .map(|b| entry([("set_id", b.set_id), ("id", b.id)]))
+            .collect();
+        let fragments: Vec<Property> = msg
+            .message
+            .fragments
+            .into_iter()
+            .map(|f| entry([("type", f.kind), ("text", f.text)]))
+            .collect();
+
+        let props: [(&str, Property); 15] = [
+            ("broadcaster_user_id", msg.broadcaster_user_id.into()),
+            ("broadcaster_user_login", msg.broadcaster_user_login.into()),
+            ("broadcaster_user_name", msg.broadcaster_user_name.into()),
+            ("user_id", msg.chatter_user_id.into()),
+            ("user_login", msg.chatter_user_login.into()),
+            ("user_name", msg.chatter_user_name.into()),
+            ("badges", badges.into()),
+            ("message", msg.message.text.into()),
+            ("fragments", fragments.into()),
+            ("message_id", msg.message_id.into()),
+            ("reply_parent_message_id", reply.parent_message_id.into()),
+            ("reply_parent_user_id", reply.parent_user_id.into()),
+            ("reply_parent_user_login", reply.parent_user_login.into()),
+            ("reply_parent_user_name", reply.parent_user_name.into()),
+            (
+                "reply_parent_message_body",
+                reply.parent_message_body.into(),
+            ),
+        ];
+        props
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect()
+    }
+}
+
+pub struct ChatProcess {
+    subscriptions: Subscriptions,
+}
+
+impl ChatProcess {
+    async fn initializer(component: ChatComponent) -> Result<Box<dyn Process + Send>> {
+        let client: HelixClient<reqwest::Client> = HelixClient::default();
+        let token = component.auth.token().await?;
+
+        let channel_id = helix::get_user_id_for(&client, &token, &component.channel).await?;
+        let subscription = Subscription::new(
+            CHAT_MESSAGE,
+            "1",
+            serde_json::json!({
+                "broadcaster_user_id": channel_id.as_str(),
+                "user_id": token.user_id.as_str(),
+            }),
+        );
+        let subscriptions =
+            component
+                .hub
+                .register(&component.id, component.auth.clone(), vec![subscription]);
+
+        Ok(Box::new(Self { subscriptions }))
+    }
+
+    fn process_chat(msg: &str) -> Result<Vec<Packet>> {
+        let frame: Frame = serde_json::from_str(msg).into_diagnostic()?;
+        let payload: ChatPayload = serde_json::from_value(frame.payload).into_diagnostic()?;
+        Ok(vec![Packet {
+            port: "message".to_string(),
+            message: payload.event.into(),
+        }])
+    }
+}
+
+#[async_trait]
+impl Process for ChatProcess {
+    async fn run(&mut self, conn: &mut Connection) -> Result<()> {
+        self.passive_run(conn).await
+    }
+
+    async fn shutdown(&mut self) -> Result<()> {
+        self.subscriptions.close().await;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PassiveProcess for ChatProcess {
+    async fn handler(&mut self) -> Result<Vec<Packet>> {
+        // errors of the subscriptions, such as a revocation, stop the process.
+        let result = match self.subscriptions.next().await? {
+            SessionEvent::Notification {
+                subscription_type,
+                frame,
+                ..
+            } if subscription_type == CHAT_MESSAGE => Self::process_chat(&frame),
+            _ => Ok(vec![]),
+        };
+        // catch and we will continue to process for next message.
+        Ok(result.unwrap_or_else(|err| {
+            tracing::warn!("chat message process error: {}", err);
+            vec![]
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::{json, Value};
+
+    /// a notification frame as twitch sends it over the websocket.
+    fn frame(event: Value) -> String {
+        json!({
+            "metadata": {
+                "message_id": "befa7b53-d79d-478f-86b9-120f112b044e",
+                "message_type": "notification",
+                "message_timestamp": "2023-11-16T10:11:12.464757833Z",
+                "subscription_type": CHAT_MESSAGE,
+                "subscription_version": "1"
+            },
+            "payload": {
+                "subscription": {
+                    "id": "0b7f3361-672b-4d39-b307-dd5b576c9b27",
+                    "status": "enabled",
+                    "type": CHAT_MESSAGE,
+                    "version": "1",
+                    "condition": {"broadcaster_user_id": "1971641", "user_id": "2914196"},
+                    "transport": {"method": "websocket", "session_id": "AQoQexAWVYKSTIu4ec_2VAxyuhAB"},
+                    "created_at": "2023-11-16T10:11:12.464757833Z",
+                    "cost": 0
+                },
+                "event": event
+            }
+        })
+        .to_string()
+    }
+
+    fn event(reply: Value) -> Value {
+        json!({
+            "broadcaster_user_id": "1971641",
+            "broadcaster_user_login": "streamer",
+            "broadcaster_user_name": "Streamer",
+            "chatter_user_id": "4145994",
+            "chatter_user_login": "viewer32",
+            "chatter_user_name": "Viewer32",
+            "message_id": "cc106a89-1814-919d-454c-f4f2f970aae7",
+            "message": {
+                "text": "Hi chat Kappa",
+                "fragments": [
+                    {"type": "text", "text": "Hi chat ", "cheermote": null, "emote": null, "mention": null},
+                    {"type": "emote", "text": "Kappa", "cheermote": null, "emote": {"id": "25", "emote_set_id": "0"}, "mention": null}
+                ]
+            },
+            "color": "#00FF7F",
+            "badges": [
+                {"set_id": "moderator", "id": "1", "info": ""},
+                {"set_id": "subscriber", "id": "12", "info": "16"}
+            ],
+            "message_type": "text",
+            "cheer": null,
+            "reply": reply,
+            "channel_points_custom_reward_id": null
+        })
+    }
+
+    fn entry(pairs: [(&str, &str); 2]) -> Property {
+        Property::Map(
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), (*v).into()))
+                .collect(),
+        )
+    }
+
+    fn process(event: Value) -> Message {
+        let mut packets = ChatProcess::process_chat(&frame(event)).unwrap();
+        assert_eq!(packets.len(), 1);
+        let packet = packets.remove(0);
+        assert_eq!(packet.port, "message");
+        for (name, _) in PROPERTIES {
+            assert!(packet.message.contains_key(*name), "{} is missing", name);
+        }
+        packet.message
+    }
+
+    #[test]
+    fn test_chat_message() {
+        let message = process(event(Value::Null));
+        assert_eq!(message["user_login"], "viewer32".into());
+        assert_eq!(message["message"], "Hi chat Kappa".into());
+        assert_eq!(
+            message["badges"],
+            Property::List(vec![
+                entry([("set_id", "moderator"), ("id", "1")]),
+                entry([("set_id", "subscriber"), ("id", "12")]),
+            ])
+        );
+        assert_eq!(
+            message["fragments"],
+            Property::List(vec![
+                entry([("type", "text"), ("text", "Hi chat ")]),
+                entry([("type", "emote"), ("text", "Kappa")]),
+            ])
+        );
+        // not a reply.
+        assert_eq!(message["reply_parent_message_id"], "".into());
+    }
+
+    #[test]
+    fn test_chat_reply() {
+        let message = process(event(json!({
+            "parent_message_id": "9a2b5a4c-0c1f-4a53-a3d4-1f1b3c3c1e1a",
+            "parent_message_body": "first!",
+            "parent_user_id": "1234",
+            "parent_user_name": "Cool_User",
+            "parent_user_login": "cool_user",
+            "thread_message_id": "9a2b5a4c-0c1f-4a53-a3d4-1f1b3c3c1e1a",
+            "thread_user_id": "1234",
+            "thread_user_name": "Cool_User",
+            "thread_user_login": "cool_user"
+        })));
+        assert_eq!(
+            message["reply_parent_message_id"],
+            "9a2b5a4c-0c1f-4a53-a3d4-1f1b3c3c1e1a".into()
+        );
+        assert_eq!(message["reply_parent_message_body"], "first!".into());
+        assert_eq!(message["reply_parent_user_login"], "cool_user".into());
+        assert_eq!(message["reply_parent_user_name"], "Cool_User".into());
+    }
+
+    #[test]
+    fn test_broken_chat_message() {
+        assert!(ChatProcess::process_chat(&frame(json!({"message": "no chatter"}))).is_err());
+    }
+}
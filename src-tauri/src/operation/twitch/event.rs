@@ -0,0 +1,1033 @@
+use miette::Result;
+use twitch_api::{
+    eventsub::{
+        channel::{
+            ChannelCheerV1, ChannelFollowV2, ChannelHypeTrainBeginV1, ChannelHypeTrainEndV1,
+            ChannelPointsCustomRewardRedemptionAddV1, ChannelPollBeginV1, ChannelPollEndV1,
+            ChannelPredictionBeginV1, ChannelPredictionEndV1, ChannelRaidV1, ChannelSubscribeV1,
+            ChannelSubscriptionGiftV1, ChannelSubscriptionMessageV1,
+        },
+        stream::{StreamOfflineV1, StreamOnlineV1},
+        Event, Message as TwitchMessage, Payload,
+    },
+    types::{SubscriptionTier, Timestamp, UserId},
+};
+
+use crate::model::PropertyType;
+use crate::operation::pipeline::{Message, Packet, Property};
+use crate::operation::twitch::Subscription;
+
+/// EventPort is an output port of the subscriber, each of them is backed by one EventSub subscription.
+pub struct EventPort {
+    pub name: &'static str,
+    pub scopes: &'static [&'static str],
+    pub properties: &'static [(&'static str, PropertyType)],
+}
+
+// properties are Text unless their type is given.
+macro_rules! typed {
+    () => {
+        PropertyType::Text
+    };
+    ($ty:ident) => {
+        PropertyType::$ty
+    };
+}
+
+macro_rules! properties {
+    ($($name:literal $(: $ty:ident)?),* $(,)?) => {
+        &[
+            ("broadcaster_user_id", PropertyType::Text),
+            ("broadcaster_user_login", PropertyType::Text),
+            ("broadcaster_user_name", PropertyType::Text),
+            $(($name, typed!($($ty)?))),*
+        ]
+    };
+}
+
+pub const EVENTS: &[EventPort] = &[
+    EventPort {
+        name: "raid",
+        // channel.raid subscriptions do not require any authorization.
+        scopes: &[],
+        properties: &[
+            ("from_broadcaster_user_id", PropertyType::Text),
+            ("from_broadcaster_user_login", PropertyType::Text),
+            ("from_broadcaster_user_name", PropertyType::Text),
+            ("to_broadcaster_user_id", PropertyType::Text),
+            ("to_broadcaster_user_login", PropertyType::Text),
+            ("to_broadcaster_user_name", PropertyType::Text),
+            ("viewers", PropertyType::I64),
+        ],
+    },
+    EventPort {
+        name: "follow",
+        scopes: &["moderator:read:followers"],
+        properties: properties!["user_id", "user_login", "user_name", "followed_at": Timestamp],
+    },
+    EventPort {
+        name: "subscribe",
+        scopes: &["channel:read:subscriptions"],
+        properties: properties!["user_id", "user_login", "user_name", "tier", "is_gift": Bool],
+    },
+    EventPort {
+        name: "subscription_gift",
+        scopes: &["channel:read:subscriptions"],
+        properties: properties![
+            "user_id",
+            "user_login",
+            "user_name",
+            "tier",
+            "total": I64,
+            "cumulative_total": I64,
+            "is_anonymous": Bool,
+        ],
+    },
+    EventPort {
+        name: "resubscribe",
+        scopes: &["channel:read:subscriptions"],
+        properties: properties![
+            "user_id",
+            "user_login",
+            "user_name",
+            "tier",
+            "message",
+            "cumulative_months": I64,
+            "streak_months": I64,
+            "duration_months": I64,
+        ],
+    },
+    EventPort {
+        name: "cheer",
+        scopes: &["bits:read"],
+        properties: properties![
+            "user_id",
+            "user_login",
+            "user_name",
+            "is_anonymous": Bool,
+            "message",
+            "bits": I64,
+        ],
+    },
+    EventPort {
+        name: "redemption",
+        scopes: &["channel:read:redemptions"],
+        properties: properties![
+            "id",
+            "user_id",
+            "user_login",
+            "user_name",
+            "user_input",
+            "reward_id",
+            "reward_title",
+            "reward_cost": I64,
+            "reward_prompt",
+            "reward": Map,
+            "redeemed_at": Timestamp,
+        ],
+    },
+    EventPort {
+        name: "stream_online",
+        scopes: &[],
+        properties: properties!["id", "type", "started_at": Timestamp],
+    },
+    EventPort {
+        name: "stream_offline",
+        scopes: &[],
+        properties: properties![],
+    },
+    EventPort {
+        name: "hype_train_begin",
+        scopes: &["channel:read:hype_train"],
+        properties: properties![
+            "id",
+            "total": I64,
+            "progress": I64,
+            "goal": I64,
+            "last_contribution_user_login",
+            "last_contribution_total": I64,
+            "started_at": Timestamp,
+            "expires_at": Timestamp,
+        ],
+    },
+    EventPort {
+        name: "hype_train_end",
+        scopes: &["channel:read:hype_train"],
+        properties: properties!["id", "level": I64, "total": I64, "started_at": Timestamp, "ended_at": Timestamp],
+    },
+    EventPort {
+        name: "poll_begin",
+        scopes: &["channel:read:polls"],
+        properties: properties!["id", "title", "choices": List, "started_at": Timestamp, "ends_at": Timestamp],
+    },
+    EventPort {
+        name: "poll_end",
+        scopes: &["channel:read:polls"],
+        properties: properties!["id", "title", "choices": List, "status", "started_at": Timestamp, "ended_at": Timestamp],
+    },
+    EventPort {
+        name: "prediction_begin",
+        scopes: &["channel:read:predictions"],
+        properties: properties!["id", "title", "outcomes": List, "started_at": Timestamp, "locks_at": Timestamp],
+    },
+    EventPort {
+        name: "prediction_end",
+        scopes: &["channel:read:predictions"],
+        properties: properties![
+            "id",
+            "title",
+            "outcomes": List,
+            "winning_outcome",
+            "status",
+            "started_at": Timestamp,
+            "ended_at": Timestamp,
+        ],
+    },
+];
+
+/// the EventSub subscription behind the `port` for the channel.
+pub fn subscription(
+    channel_id: &UserId,
+    moderator: &UserId,
+    port: &str,
+) -> Result<Option<Subscription>> {
+    let id = channel_id.clone();
+    let sub = match port {
+        "raid" => Subscription::of(&ChannelRaidV1::to_broadcaster_user_id(id)),
+        "follow" => Subscription::of(&ChannelFollowV2::new(id, moderator.clone())),
+        "subscribe" => Subscription::of(&ChannelSubscribeV1::broadcaster_user_id(id)),
+        "subscription_gift" => {
+            Subscription::of(&ChannelSubscriptionGiftV1::broadcaster_user_id(id))
+        }
+        "resubscribe" => Subscription::of(&ChannelSubscriptionMessageV1::broadcaster_user_id(id)),
+        "cheer" => Subscription::of(&ChannelCheerV1::broadcaster_user_id(id)),
+        "redemption" => {
+            Subscription::of(&ChannelPointsCustomRewardRedemptionAddV1::broadcaster_user_id(id))
+        }
+        "stream_online" => Subscription::of(&StreamOnlineV1::broadcaster_user_id(id)),
+        "stream_offline" => Subscription::of(&StreamOfflineV1::broadcaster_user_id(id)),
+        "hype_train_begin" => Subscription::of(&ChannelHypeTrainBeginV1::broadcaster_user_id(id)),
+        "hype_train_end" => Subscription::of(&ChannelHypeTrainEndV1::broadcaster_user_id(id)),
+        "poll_begin" => Subscription::of(&ChannelPollBeginV1::broadcaster_user_id(id)),
+        "poll_end" => Subscription::of(&ChannelPollEndV1::broadcaster_user_id(id)),
+        "prediction_begin" => Subscription::of(&ChannelPredictionBeginV1::broadcaster_user_id(id)),
+        "prediction_end" => Subscription::of(&ChannelPredictionEndV1::broadcaster_user_id(id)),
+        // unknown ports have nothing to subscribe.
+        _ => return Ok(None),
+    };
+    sub.map(Some)
+}
+
+fn text(v: impl AsRef<str>) -> Property {
+    Property::Text(v.as_ref().to_string())
+}
+
+/// the name of the value as EventSub gives it, e.g. "watch_party" for a stream type.
+/// twitch_api serializes some of them in upper case as Helix does, e.g. "COMPLETED".
+fn name(v: &impl serde::Serialize) -> Property {
+    let name = serde_json::to_value(v)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_lowercase));
+    text(name.unwrap_or_default())
+}
+
+fn optional(v: &Option<impl AsRef<str>>) -> Property {
+    text(v.as_ref().map(|v| v.as_ref()).unwrap_or_default())
+}
+
+fn flag(v: bool) -> Property {
+    Property::Bool(v)
+}
+
+fn timestamp(v: &Timestamp) -> Property {
+    Property::Timestamp(v.as_str().to_string())
+}
+
+fn tier(v: &SubscriptionTier) -> Property {
+    match v {
+        SubscriptionTier::Tier1 => text("1000"),
+        SubscriptionTier::Tier2 => text("2000"),
+        SubscriptionTier::Tier3 => text("3000"),
+        SubscriptionTier::Prime => text("Prime"),
+        SubscriptionTier::Other(other) => text(other),
+    }
+}
+
+macro_rules! broadcaster {
+    ($msg:expr) => {
+        [
+            ("broadcaster_user_id", text(&$msg.broadcaster_user_id)),
+            ("broadcaster_user_login", text(&$msg.broadcaster_user_login)),
+            ("broadcaster_user_name", text(&$msg.broadcaster_user_name)),
+        ]
+    };
+}
+
+fn with<const N: usize, const M: usize>(
+    head: [(&'static str, Property); N],
+    tail: [(&'static str, Property); M],
+) -> Vec<(&'static str, Property)> {
+    head.into_iter().chain(tail).collect()
+}
+
+fn packet(port: &str, props: Vec<(&str, Property)>) -> Vec<Packet> {
+    let mut message = Message::new();
+    message.insert("event".to_string(), text(port));
+    for (name, prop) in props {
+        message.insert(name.to_string(), prop);
+    }
+    vec![Packet {
+        port: port.to_string(),
+        message,
+    }]
+}
+
+/// converts an EventSub notification into packets on the matching output port.
+pub fn packets(payload: &Event) -> Vec<Packet> {
+    use Event::*;
+    use TwitchMessage::Notification;
+
+    match payload {
+        ChannelRaidV1(Payload {
+            message: Notification(msg),
+            ..
+        }) => packet(
+            "raid",
+            vec![
+                (
+                    "from_broadcaster_user_id",
+                    text(&msg.from_broadcaster_user_id),
+                ),
+                (
+                    "from_broadcaster_user_login",
+                    text(&msg.from_broadcaster_user_login),
+                ),
+                (
+                    "from_broadcaster_user_name",
+                    text(&msg.from_broadcaster_user_name),
+                ),
+                ("to_broadcaster_user_id", text(&msg.to_broadcaster_user_id)),
+                (
+                    "to_broadcaster_user_login",
+                    text(&msg.to_broadcaster_user_login),
+                ),
+                (
+                    "to_broadcaster_user_name",
+                    text(&msg.to_broadcaster_user_name),
+                ),
+                ("viewers", Property::I64(msg.viewers)),
+            ],
+        ),
+        ChannelFollowV2(Payload {
+            message: Notification(msg),
+            ..
+        }) => packet(
+            "follow",
+            with(
+                broadcaster!(msg),
+                [
+                    ("user_id", text(&msg.user_id)),
+                    ("user_login", text(&msg.user_login)),
+                    ("user_name", text(&msg.user_name)),
+                    ("followed_at", timestamp(&msg.followed_at)),
+                ],
+            ),
+        ),
+        ChannelSubscribeV1(Payload {
+            message: Notification(msg),
+            ..
+        }) => packet(
+            "subscribe",
+            with(
+                broadcaster!(msg),
+                [
+                    ("user_id", text(&msg.user_id)),
+                    ("user_login", text(&msg.user_login)),
+                    ("user_name", text(&msg.user_name)),
+                    ("tier", tier(&msg.tier)),
+                    ("is_gift", flag(msg.is_gift)),
+                ],
+            ),
+        ),
+        ChannelSubscriptionGiftV1(Payload {
+            message: Notification(msg),
+            ..
+        }) => packet(
+            "subscription_gift",
+            with(
+                broadcaster!(msg),
+                [
+                    ("user_id", optional(&msg.user_id)),
+                    ("user_login", optional(&msg.user_login)),
+                    ("user_name", optional(&msg.user_name)),
+                    ("tier", tier(&msg.tier)),
+                    ("total", Property::I64(msg.total)),
+                    (
+                        "cumulative_total",
+                        Property::I64(msg.cumulative_total.unwrap_or_default()),
+                    ),
+                    ("is_anonymous", flag(msg.is_anonymous)),
+                ],
+            ),
+        ),
+        ChannelSubscriptionMessageV1(Payload {
+            message: Notification(msg),
+            ..
+        }) => packet(
+            "resubscribe",
+            with(
+                broadcaster!(msg),
+                [
+                    ("user_id", text(&msg.user_id)),
+                    ("user_login", text(&msg.user_login)),
+                    ("user_name", text(&msg.user_name)),
+                    ("tier", tier(&msg.tier)),
+                    ("message", text(&msg.message.text)),
+                    ("cumulative_months", Property::I64(msg.cumulative_months)),
+                    (
+                        "streak_months",
+                        Property::I64(msg.streak_months.unwrap_or_default()),
+                    ),
+                    ("duration_months", Property::I64(msg.duration_months)),
+                ],
+            ),
+        ),
+        ChannelCheerV1(Payload {
+            message: Notification(msg),
+            ..
+        }) => packet(
+            "cheer",
+            with(
+                broadcaster!(msg),
+                [
+                    ("user_id", optional(&msg.user_id)),
+                    ("user_login", optional(&msg.user_login)),
+                    ("user_name", optional(&msg.user_name)),
+                    ("is_anonymous", flag(msg.is_anonymous)),
+                    ("message", text(&msg.message)),
+                    ("bits", Property::I64(msg.bits)),
+                ],
+            ),
+        ),
+        ChannelPointsCustomRewardRedemptionAddV1(Payload {
+            message: Notification(msg),
+            ..
+        }) => packet(
+            "redemption",
+            with(
+                broadcaster!(msg),
+                [
+                    ("id", text(&msg.id)),
+                    ("user_id", text(&msg.user_id)),
+                    ("user_login", text(&msg.user_login)),
+                    ("user_name", text(&msg.user_name)),
+                    ("user_input", text(&msg.user_input)),
+                    ("reward_id", text(&msg.reward.id)),
+                    ("reward_title", text(&msg.reward.title)),
+                    ("reward_cost", Property::I64(msg.reward.cost)),
+                    ("reward_prompt", text(&msg.reward.prompt)),
+                    (
+                        "reward",
+                        Property::Map(Message::from([
+                            ("id".to_string(), text(&msg.reward.id)),
+                            ("title".to_string(), text(&msg.reward.title)),
+                            ("cost".to_string(), Property::I64(msg.reward.cost)),
+                            ("prompt".to_string(), text(&msg.reward.prompt)),
+                        ])),
+                    ),
+                    ("redeemed_at", timestamp(&msg.redeemed_at)),
+                ],
+            ),
+        ),
+        StreamOnlineV1(Payload {
+            message: Notification(msg),
+            ..
+        }) => packet(
+            "stream_online",
+            with(
+                broadcaster!(msg),
+                [
+                    ("id", text(&msg.id)),
+                    ("type", name(&msg.type_)),
+                    ("started_at", timestamp(&msg.started_at)),
+                ],
+            ),
+        ),
+        StreamOfflineV1(Payload {
+            message: Notification(msg),
+            ..
+        }) => packet("stream_offline", with(broadcaster!(msg), [])),
+        ChannelHypeTrainBeginV1(Payload {
+            message: Notification(msg),
+            ..
+        }) => packet(
+            "hype_train_begin",
+            with(
+                broadcaster!(msg),
+                [
+                    ("id", text(&msg.id)),
+                    ("total", Property::I64(msg.total)),
+                    ("progress", Property::I64(msg.progress)),
+                    ("goal", Property::I64(msg.goal)),
+                    (
+                        "last_contribution_user_login",
+                        text(&msg.last_contribution.user_login),
+                    ),
+                    (
+                        "last_contribution_total",
+                        Property::I64(msg.last_contribution.total),
+                    ),
+                    ("started_at", timestamp(&msg.started_at)),
+                    ("expires_at", timestamp(&msg.expires_at)),
+                ],
+            ),
+        ),
+        ChannelHypeTrainEndV1(Payload {
+            message: Notification(msg),
+            ..
+        }) => packet(
+            "hype_train_end",
+            with(
+                broadcaster!(msg),
+                [
+                    ("id", text(&msg.id)),
+                    ("level", Property::I64(msg.level)),
+                    ("total", Property::I64(msg.total)),
+                    ("started_at", timestamp(&msg.started_at)),
+                    ("ended_at", timestamp(&msg.ended_at)),
+                ],
+            ),
+        ),
+        ChannelPollBeginV1(Payload {
+            message: Notification(msg),
+            ..
+        }) => packet(
+            "poll_begin",
+            with(
+                broadcaster!(msg),
+                [
+                    ("id", text(&msg.id)),
+                    ("title", text(&msg.title)),
+                    ("choices", titles(msg.choices.iter().map(|c| &c.title))),
+                    ("started_at", timestamp(&msg.started_at)),
+                    ("ends_at", timestamp(&msg.ends_at)),
+                ],
+            ),
+        ),
+        ChannelPollEndV1(Payload {
+            message: Notification(msg),
+            ..
+        }) => packet(
+            "poll_end",
+            with(
+                broadcaster!(msg),
+                [
+                    ("id", text(&msg.id)),
+                    ("title", text(&msg.title)),
+                    ("choices", titles(msg.choices.iter().map(|c| &c.title))),
+                    ("status", name(&msg.status)),
+                    ("started_at", timestamp(&msg.started_at)),
+                    ("ended_at", timestamp(&msg.ended_at)),
+                ],
+            ),
+        ),
+        ChannelPredictionBeginV1(Payload {
+            message: Notification(msg),
+            ..
+        }) => packet(
+            "prediction_begin",
+            with(
+                broadcaster!(msg),
+                [
+                    ("id", text(&msg.id)),
+                    ("title", text(&msg.title)),
+                    ("outcomes", titles(msg.outcomes.iter().map(|o| &o.title))),
+                    ("started_at", timestamp(&msg.started_at)),
+                    ("locks_at", timestamp(&msg.locks_at)),
+                ],
+            ),
+        ),
+        ChannelPredictionEndV1(Payload {
+            message: Notification(msg),
+            ..
+        }) => {
+            let winner = msg
+                .outcomes
+                .iter()
+                .find(|o| o.id == msg.winning_outcome_id.as_str())
+                .map(|o| o.title.clone())
+                .unwrap_or_default();
+            packet(
+                "prediction_end",
+                with(
+                    broadcaster!(msg),
+                    [
+                        ("id", text(&msg.id)),
+                        ("title", text(&msg.title)),
+                        ("outcomes", titles(msg.outcomes.iter().map(|o| &o.title))),
+                        ("winning_outcome", text(winner)),
+                        ("status", name(&msg.status)),
+                        ("started_at", timestamp(&msg.started_at)),
+                        ("ended_at", timestamp(&msg.ended_at)),
+                    ],
+                ),
+            )
+        }
+        _ => vec![],
+    }
+}
+
+fn titles<'a>(titles: impl Iterator<Item = &'a String>) -> Property {
+    titles.cloned().collect::<Vec<_>>().into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::{json, Value};
+
+    /// parses a notification as twitch sends it, built around the given event.
+    fn parse(kind: &str, version: &str, condition: Value, event: Value) -> Event {
+        let payload = json!({
+            "subscription": {
+                "id": "f1c2a387-161a-49f9-a165-0f21d7a4e1c4",
+                "type": kind,
+                "version": version,
+                "status": "enabled",
+                "cost": 0,
+                "condition": condition,
+                "transport": {
+                    "method": "webhook",
+                    "callback": "https://example.com/webhooks/callback"
+                },
+                "created_at": "2019-11-16T10:11:12.123Z"
+            },
+            "event": event,
+        });
+        Event::parse(&payload.to_string()).unwrap()
+    }
+
+    fn type_of(prop: &Property) -> PropertyType {
+        match prop {
+            Property::Text(_) => PropertyType::Text,
+            Property::I64(_) => PropertyType::I64,
+            Property::F64(_) => PropertyType::F64,
+            Property::Bool(_) => PropertyType::Bool,
+            Property::Timestamp(_) => PropertyType::Timestamp,
+            Property::List(_) => PropertyType::List,
+            Property::Map(_) => PropertyType::Map,
+        }
+    }
+
+    /// converts the event, and checks that the packet has the properties its port declares.
+    fn convert(port: &str, event: &Event) -> Message {
+        let mut packets = packets(event);
+        assert_eq!(packets.len(), 1);
+        let Packet {
+            port: sent,
+            message,
+        } = packets.remove(0);
+        assert_eq!(sent, port);
+        assert_eq!(message["event"], text(port));
+
+        let declared = EVENTS.iter().find(|e| e.name == port).unwrap();
+        for (name, ty) in declared.properties {
+            let prop = message
+                .get(*name)
+                .unwrap_or_else(|| panic!("{} lacks {}", port, name));
+            assert_eq!(type_of(prop), *ty, "{} of {}", name, port);
+        }
+        assert_eq!(message.len(), declared.properties.len() + 1);
+        message
+    }
+
+    fn channel() -> Value {
+        json!({"broadcaster_user_id": "1337"})
+    }
+
+    fn timestamp(v: &str) -> Property {
+        Property::Timestamp(v.to_string())
+    }
+
+    #[test]
+    fn test_raid() {
+        let event = parse(
+            "channel.raid",
+            "1",
+            json!({"to_broadcaster_user_id": "1337"}),
+            json!({
+                "from_broadcaster_user_id": "1234",
+                "from_broadcaster_user_login": "cool_user",
+                "from_broadcaster_user_name": "Cool_User",
+                "to_broadcaster_user_id": "1337",
+                "to_broadcaster_user_login": "cooler_user",
+                "to_broadcaster_user_name": "Cooler_User",
+                "viewers": 9001
+            }),
+        );
+        let message = convert("raid", &event);
+        assert_eq!(message["from_broadcaster_user_login"], text("cool_user"));
+        assert_eq!(message["viewers"], Property::I64(9001));
+    }
+
+    #[test]
+    fn test_follow() {
+        let event = parse(
+            "channel.follow",
+            "2",
+            json!({"broadcaster_user_id": "1337", "moderator_user_id": "1337"}),
+            json!({
+                "user_id": "1234",
+                "user_login": "cool_user",
+                "user_name": "Cool_User",
+                "broadcaster_user_id": "1337",
+                "broadcaster_user_login": "cooler_user",
+                "broadcaster_user_name": "Cooler_User",
+                "followed_at": "2020-07-15T18:16:11.17106713Z"
+            }),
+        );
+        let message = convert("follow", &event);
+        assert_eq!(message["user_name"], text("Cool_User"));
+        assert_eq!(message["broadcaster_user_login"], text("cooler_user"));
+        assert_eq!(
+            message["followed_at"],
+            timestamp("2020-07-15T18:16:11.17106713Z")
+        );
+    }
+
+    #[test]
+    fn test_subscriptions() {
+        let event = parse(
+            "channel.subscribe",
+            "1",
+            channel(),
+            json!({
+                "user_id": "1234",
+                "user_login": "cool_user",
+                "user_name": "Cool_User",
+                "broadcaster_user_id": "1337",
+                "broadcaster_user_login": "cooler_user",
+                "broadcaster_user_name": "Cooler_User",
+                "tier": "1000",
+                "is_gift": false
+            }),
+        );
+        let message = convert("subscribe", &event);
+        assert_eq!(message["tier"], text("1000"));
+        assert_eq!(message["is_gift"], Property::Bool(false));
+
+        // the gifter is not given if it is anonymous.
+        let event = parse(
+            "channel.subscription.gift",
+            "1",
+            channel(),
+            json!({
+                "user_id": null,
+                "user_login": null,
+                "user_name": null,
+                "broadcaster_user_id": "1337",
+                "broadcaster_user_login": "cooler_user",
+                "broadcaster_user_name": "Cooler_User",
+                "total": 2,
+                "tier": "3000",
+                "cumulative_total": null,
+                "is_anonymous": true
+            }),
+        );
+        let message = convert("subscription_gift", &event);
+        assert_eq!(message["user_name"], text(""));
+        assert_eq!(message["tier"], text("3000"));
+        assert_eq!(message["total"], Property::I64(2));
+        assert_eq!(message["cumulative_total"], Property::I64(0));
+        assert_eq!(message["is_anonymous"], Property::Bool(true));
+
+        let event = parse(
+            "channel.subscription.message",
+            "1",
+            channel(),
+            json!({
+                "user_id": "1234",
+                "user_login": "cool_user",
+                "user_name": "Cool_User",
+                "broadcaster_user_id": "1337",
+                "broadcaster_user_login": "cooler_user",
+                "broadcaster_user_name": "Cooler_User",
+                "tier": "1000",
+                "message": {
+                    "text": "Love the stream! FevziGG",
+                    "emotes": [{"begin": 23, "end": 30, "id": "302976485"}]
+                },
+                "cumulative_months": 15,
+                "streak_months": null,
+                "duration_months": 6
+            }),
+        );
+        let message = convert("resubscribe", &event);
+        assert_eq!(message["message"], text("Love the stream! FevziGG"));
+        assert_eq!(message["cumulative_months"], Property::I64(15));
+        assert_eq!(message["streak_months"], Property::I64(0));
+        assert_eq!(message["duration_months"], Property::I64(6));
+    }
+
+    #[test]
+    fn test_cheer() {
+        let event = parse(
+            "channel.cheer",
+            "1",
+            channel(),
+            json!({
+                "is_anonymous": false,
+                "user_id": "1234",
+                "user_login": "cool_user",
+                "user_name": "Cool_User",
+                "broadcaster_user_id": "1337",
+                "broadcaster_user_login": "cooler_user",
+                "broadcaster_user_name": "Cooler_User",
+                "message": "pogchamp",
+                "bits": 1000
+            }),
+        );
+        let message = convert("cheer", &event);
+        assert_eq!(message["user_login"], text("cool_user"));
+        assert_eq!(message["message"], text("pogchamp"));
+        assert_eq!(message["bits"], Property::I64(1000));
+    }
+
+    #[test]
+    fn test_redemption() {
+        let event = parse(
+            "channel.channel_points_custom_reward_redemption.add",
+            "1",
+            channel(),
+            json!({
+                "id": "1234",
+                "broadcaster_user_id": "1337",
+                "broadcaster_user_login": "cool_user",
+                "broadcaster_user_name": "Cool_User",
+                "user_id": "9001",
+                "user_login": "cooler_user",
+                "user_name": "Cooler_User",
+                "user_input": "pogchamp",
+                "status": "unfulfilled",
+                "reward": {
+                    "id": "9001",
+                    "title": "title",
+                    "cost": 100,
+                    "prompt": "reward prompt"
+                },
+                "redeemed_at": "2020-07-15T17:16:03.17106713Z"
+            }),
+        );
+        let message = convert("redemption", &event);
+        assert_eq!(message["user_input"], text("pogchamp"));
+        assert_eq!(message["reward_title"], text("title"));
+        assert_eq!(message["reward_cost"], Property::I64(100));
+        let Property::Map(reward) = &message["reward"] else {
+            panic!("reward is not a map");
+        };
+        assert_eq!(reward["prompt"], text("reward prompt"));
+        assert_eq!(reward["cost"], Property::I64(100));
+    }
+
+    #[test]
+    fn test_stream() {
+        let event = parse(
+            "stream.online",
+            "1",
+            channel(),
+            json!({
+                "id": "9001",
+                "broadcaster_user_id": "1337",
+                "broadcaster_user_login": "cool_user",
+                "broadcaster_user_name": "Cool_User",
+                "type": "live",
+                "started_at": "2020-10-11T10:11:12.123Z"
+            }),
+        );
+        let message = convert("stream_online", &event);
+        assert_eq!(message["type"], text("live"));
+        assert_eq!(message["started_at"], timestamp("2020-10-11T10:11:12.123Z"));
+
+        let event = parse(
+            "stream.online",
+            "1",
+            channel(),
+            json!({
+                "id": "9001",
+                "broadcaster_user_id": "1337",
+                "broadcaster_user_login": "cool_user",
+                "broadcaster_user_name": "Cool_User",
+                "type": "watch_party",
+                "started_at": "2020-10-11T10:11:12.123Z"
+            }),
+        );
+        let message = convert("stream_online", &event);
+        assert_eq!(message["type"], text("watch_party"));
+
+        let event = parse(
+            "stream.offline",
+            "1",
+            channel(),
+            json!({
+                "broadcaster_user_id": "1337",
+                "broadcaster_user_login": "cool_user",
+                "broadcaster_user_name": "Cool_User"
+            }),
+        );
+        let message = convert("stream_offline", &event);
+        assert_eq!(message["broadcaster_user_name"], text("Cool_User"));
+    }
+
+    #[test]
+    fn test_hype_train() {
+        let event = parse(
+            "channel.hype_train.begin",
+            "1",
+            channel(),
+            json!({
+                "id": "1b0AsbInCHZW2SQFQkCzqN07Ib2",
+                "broadcaster_user_id": "1337",
+                "broadcaster_user_login": "cool_user",
+                "broadcaster_user_name": "Cool_User",
+                "total": 137,
+                "progress": 137,
+                "goal": 500,
+                "top_contributions": [
+                    {"user_id": "123", "user_login": "pogchamp", "user_name": "PogChamp", "type": "bits", "total": 50}
+                ],
+                "last_contribution": {"user_id": "123", "user_login": "pogchamp", "user_name": "PogChamp", "type": "bits", "total": 50},
+                "started_at": "2020-07-15T17:16:03.17106713Z",
+                "expires_at": "2020-07-15T17:16:11.17106713Z"
+            }),
+        );
+        let message = convert("hype_train_begin", &event);
+        assert_eq!(message["goal"], Property::I64(500));
+        assert_eq!(message["last_contribution_user_login"], text("pogchamp"));
+        assert_eq!(message["last_contribution_total"], Property::I64(50));
+
+        let event = parse(
+            "channel.hype_train.end",
+            "1",
+            channel(),
+            json!({
+                "id": "1b0AsbInCHZW2SQFQkCzqN07Ib2",
+                "broadcaster_user_id": "1337",
+                "broadcaster_user_login": "cool_user",
+                "broadcaster_user_name": "Cool_User",
+                "level": 2,
+                "total": 137,
+                "top_contributions": [
+                    {"user_id": "123", "user_login": "pogchamp", "user_name": "PogChamp", "type": "bits", "total": 50}
+                ],
+                "started_at": "2020-07-15T17:16:03.17106713Z",
+                "ended_at": "2020-07-15T17:16:11.17106713Z",
+                "cooldown_ends_at": "2020-07-15T18:16:11.17106713Z"
+            }),
+        );
+        let message = convert("hype_train_end", &event);
+        assert_eq!(message["level"], Property::I64(2));
+        assert_eq!(
+            message["ended_at"],
+            timestamp("2020-07-15T17:16:11.17106713Z")
+        );
+    }
+
+    #[test]
+    fn test_poll() {
+        let event = parse(
+            "channel.poll.begin",
+            "1",
+            channel(),
+            json!({
+                "id": "1243456",
+                "broadcaster_user_id": "1337",
+                "broadcaster_user_login": "cool_user",
+                "broadcaster_user_name": "Cool_User",
+                "title": "Aren’t shoes just really hard socks?",
+                "choices": [
+                    {"id": "123", "title": "Yeah!"},
+                    {"id": "124", "title": "No!"}
+                ],
+                "bits_voting": {"is_enabled": true, "amount_per_vote": 10},
+                "channel_points_voting": {"is_enabled": true, "amount_per_vote": 10},
+                "started_at": "2020-07-15T17:16:03.17106713Z",
+                "ends_at": "2020-07-15T17:16:08.17106713Z"
+            }),
+        );
+        let message = convert("poll_begin", &event);
+        assert_eq!(message["choices"], vec!["Yeah!", "No!"].into());
+
+        let event = parse(
+            "channel.poll.end",
+            "1",
+            channel(),
+            json!({
+                "id": "1243456",
+                "broadcaster_user_id": "1337",
+                "broadcaster_user_login": "cool_user",
+                "broadcaster_user_name": "Cool_User",
+                "title": "Aren’t shoes just really hard socks?",
+                "choices": [
+                    {"id": "123", "title": "Blue", "bits_votes": 50, "channel_points_votes": 70, "votes": 120},
+                    {"id": "124", "title": "Yellow", "bits_votes": 100, "channel_points_votes": 40, "votes": 140}
+                ],
+                "bits_voting": {"is_enabled": true, "amount_per_vote": 10},
+                "channel_points_voting": {"is_enabled": true, "amount_per_vote": 10},
+                "status": "completed",
+                "started_at": "2020-07-15T17:16:03.17106713Z",
+                "ended_at": "2020-07-15T17:16:11.17106713Z"
+            }),
+        );
+        let message = convert("poll_end", &event);
+        assert_eq!(message["choices"], vec!["Blue", "Yellow"].into());
+        assert_eq!(message["status"], text("completed"));
+    }
+
+    #[test]
+    fn test_prediction() {
+        let event = parse(
+            "channel.prediction.begin",
+            "1",
+            channel(),
+            json!({
+                "id": "1243456",
+                "broadcaster_user_id": "1337",
+                "broadcaster_user_login": "cool_user",
+                "broadcaster_user_name": "Cool_User",
+                "title": "Aren’t shoes just really hard socks?",
+                "outcomes": [
+                    {"id": "1243456", "title": "Yeah!", "color": "blue"},
+                    {"id": "2243456", "title": "No!", "color": "pink"}
+                ],
+                "started_at": "2020-07-15T17:16:03.17106713Z",
+                "locks_at": "2020-07-15T17:21:03.17106713Z"
+            }),
+        );
+        let message = convert("prediction_begin", &event);
+        assert_eq!(message["outcomes"], vec!["Yeah!", "No!"].into());
+
+        let event = parse(
+            "channel.prediction.end",
+            "1",
+            channel(),
+            json!({
+                "id": "1243456",
+                "broadcaster_user_id": "1337",
+                "broadcaster_user_login": "cool_user",
+                "broadcaster_user_name": "Cool_User",
+                "title": "Aren’t shoes just really hard socks?",
+                "winning_outcome_id": "22435",
+                "outcomes": [
+                    {"id": "12345", "title": "Yeah!", "color": "blue", "users": 2, "channel_points": 15000, "top_predictors": []},
+                    {"id": "22435", "title": "No!", "color": "pink", "users": 2, "channel_points": 200, "top_predictors": []}
+                ],
+                "status": "resolved",
+                "started_at": "2020-07-15T17:16:03.17106713Z",
+                "ended_at": "2020-07-15T17:16:11.17106713Z"
+            }),
+        );
+        let message = convert("prediction_end", &event);
+        assert_eq!(message["winning_outcome"], text("No!"));
+        assert_eq!(message["status"], text("resolved"));
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use futures::{FutureExt, StreamExt};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use serde::Deserialize;
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::Message as WSMessage;
+
+use crate::model::error::MrDamianError;
+
+pub type WSConnection =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// time to wait for the welcome message, before twitch tells the keepalive timeout.
+const WELCOME_TIMEOUT: Duration = Duration::from_secs(10);
+/// latency allowed on top of the keepalive timeout.
+const KEEPALIVE_GRACE: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Deserialize)]
+struct Frame {
+    metadata: Metadata,
+    #[serde(default)]
+    payload: Payload,
+}
+
+#[derive(Debug, Deserialize)]
+struct Metadata {
+    message_id: String,
+    message_type: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Payload {
+    #[serde(default)]
+    session: Option<SessionPayload>,
+    #[serde(default)]
+    subscription: Option<SubscriptionPayload>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SessionPayload {
+    id: String,
+    #[serde(default)]
+    keepalive_timeout_seconds: Option<u64>,
+    #[serde(default)]
+    reconnect_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubscriptionPayload {
+    id: String,
+    #[serde(rename = "type")]
+    kind: String,
+    status: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionEvent {
+    /// a new session is started, the subscriptions have to be created for the id.
+    /// this is not sent when twitch moves the session to another connection,
+    /// since the subscriptions are kept then.
+    Welcome(String),
+    /// a notification, with the raw frame to be parsed by the receiver.
+    Notification {
+        message_id: String,
+        subscription_id: String,
+        subscription_type: String,
+        frame: String,
+    },
+    /// twitch revoked the subscription, and nothing is notified for it any more.
+    Revocation {
+        subscription_id: String,
+        subscription_type: String,
+        status: String,
+    },
+}
+
+enum Received {
+    Event(SessionEvent),
+    Reconnect(url::Url),
+    Keepalive,
+}
+
+/// Session keeps an EventSub websocket session alive.
+/// a stalled or lost connection is replaced by a new session,
+/// and a reconnect request moves the session to the given url.
+pub struct Session {
+    url: url::Url,
+    socket: WSConnection,
+    keepalive: Duration,
+    /// notifications received on the old connection while moving the session.
+    pending: VecDeque<SessionEvent>,
+}
+
+impl Session {
+    pub async fn connect_to(url: url::Url) -> Result<Self> {
+        let socket = connect(&url).await?;
+        Ok(Self {
+            url,
+            socket,
+            keepalive: WELCOME_TIMEOUT,
+            pending: VecDeque::new(),
+        })
+    }
+
+    pub async fn next(&mut self) -> Result<SessionEvent> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(event);
+            }
+            let Some(text) = receive(&mut self.socket, self.keepalive).await else {
+                // the subscriptions are gone along with the session.
+                self.reset().await?;
+                continue;
+            };
+            match self.parse(&text)? {
+                Received::Event(event) => return Ok(event),
+                Received::Reconnect(url) => self.migrate(url).await?,
+                Received::Keepalive => (),
+            }
+        }
+    }
+
+    /// closes the connection with a close frame.
+    pub async fn close(mut self) {
+        if let Err(err) = self.socket.close(None).await {
+            tracing::debug!("failed to close the eventsub connection: {}", err);
+        }
+    }
+
+    async fn reset(&mut self) -> Result<()> {
+        tracing::info!("starting a new eventsub session");
+        self.socket = connect(&self.url).await?;
+        self.keepalive = WELCOME_TIMEOUT;
+        Ok(())
+    }
+
+    /// moves the session to the url, reading the old connection until the new one is welcomed.
+    async fn migrate(&mut self, url: url::Url) -> Result<()> {
+        tracing::info!("moving the eventsub session to {}", url);
+        let mut next = match connect(&url).await {
+            Ok(next) => next,
+            Err(err) => {
+                tracing::warn!("failed to move the eventsub session: {:?}", err);
+                return self.reset().await;
+            }
+        };
+
+        let mut old_open = true;
+        loop {
+            tokio::select! {
+                text = receive(&mut next, WELCOME_TIMEOUT) => {
+                    let Some(text) = text else {
+                        tracing::warn!("the new eventsub connection was lost before the welcome");
+                        return self.reset().await;
+                    };
+                    if let Received::Event(SessionEvent::Welcome(_)) = self.parse(&text)? {
+                        break;
+                    }
+                }
+                text = receive(&mut self.socket, self.keepalive), if old_open => {
+                    match text {
+                        Some(text) => self.keep(&text)?,
+                        None => old_open = false,
+                    }
+                }
+            }
+        }
+
+        let mut old = std::mem::replace(&mut self.socket, next);
+        // take what was already sent on the old connection before closing it.
+        while old_open {
+            match old.next().now_or_never() {
+                Some(Some(Ok(WSMessage::Text(text)))) => self.keep(&text)?,
+                Some(Some(Ok(_))) => (),
+                _ => old_open = false,
+            }
+        }
+        old.close(None).await.ok();
+        Ok(())
+    }
+
+    /// keeps the notifications of the old connection while moving the session.
+    fn keep(&mut self, text: &str) -> Result<()> {
+        if let Received::Event(event) = self.parse(text)? {
+            self.pending.push_back(event);
+        }
+        Ok(())
+    }
+
+    fn parse(&mut self, text: &str) -> Result<Received> {
+        let frame: Frame = serde_json::from_str(text)
+            .into_diagnostic()
+            .wrap_err("invalid eventsub message")?;
+        let received = match frame.metadata.message_type.as_str() {
+            "session_welcome" => {
+                let session = frame
+                    .payload
+                    .session
+                    .ok_or(MrDamianError::MessageKeyNotFound)?;
+                if let Some(secs) = session.keepalive_timeout_seconds {
+                    self.keepalive = Duration::from_secs(secs);
+                }
+                Received::Event(SessionEvent::Welcome(session.id))
+            }
+            "session_reconnect" => {
+                let url = frame
+                    .payload
+                    .session
+                    .and_then(|s| s.reconnect_url)
+                    .ok_or(MrDamianError::MessageKeyNotFound)?;
+                Received::Reconnect(url.parse().into_diagnostic()?)
+            }
+            "notification" => {
+                let sub = frame
+                    .payload
+                    .subscription
+                    .ok_or(MrDamianError::MessageKeyNotFound)?;
+                Received::Event(SessionEvent::Notification {
+                    message_id: frame.metadata.message_id,
+                    subscription_id: sub.id,
+                    subscription_type: sub.kind,
+                    frame: text.to_string(),
+                })
+            }
+            "revocation" => {
+                let sub = frame
+                    .payload
+                    .subscription
+                    .ok_or(MrDamianError::MessageKeyNotFound)?;
+                Received::Event(SessionEvent::Revocation {
+                    subscription_id: sub.id,
+                    subscription_type: sub.kind,
+                    status: sub.status,
+                })
+            }
+            _ => Received::Keepalive,
+        };
+        Ok(received)
+    }
+}
+
+async fn connect(url: &url::Url) -> Result<WSConnection> {
+    let config = tokio_tungstenite::tungstenite::protocol::WebSocketConfig::default();
+
+    let (socket, _) = tokio_tungstenite::connect_async_with_config(url.clone(), Some(config))
+        .await
+        .into_diagnostic()
+        .wrap_err("Cannot connect twitch event server host")?;
+
+    Ok(socket)
+}
+
+/// returns the next text message, or None if the connection is lost or
+/// nothing arrives within the keepalive timeout.
+async fn receive(socket: &mut WSConnection, keepalive: Duration) -> Option<String> {
+    let deadline = Instant::now() + keepalive + KEEPALIVE_GRACE;
+    loop {
+        match tokio::time::timeout_at(deadline, socket.next()).await {
+            Ok(Some(Ok(WSMessage::Text(text)))) => return Some(text),
+            Ok(Some(Ok(WSMessage::Close(frame)))) => {
+                tracing::info!("eventsub connection was closed: {:?}", frame);
+                return None;
+            }
+            // pings are answered by tungstenite, and do not count as keepalive.
+            Ok(Some(Ok(_))) => (),
+            Ok(Some(Err(err))) => {
+                tracing::warn!("eventsub connection error: {}", err);
+                return None;
+            }
+            Ok(None) => return None,
+            Err(_) => {
+                tracing::warn!("no eventsub message within {:?}", keepalive);
+                return None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(super) mod test {
+    use super::*;
+    use futures::SinkExt;
+    use tokio::net::TcpListener;
+
+    pub fn frame(message_type: &str, message_id: &str, payload: serde_json::Value) -> String {
+        serde_json::json!({
+            "metadata": {
+                "message_id": message_id,
+                "message_type": message_type,
+                "message_timestamp": "2023-07-19T14:56:51.634234626Z",
+                "subscription_type": "channel.raid",
+            },
+            "payload": payload,
+        })
+        .to_string()
+    }
+
+    pub fn welcome(id: &str, keepalive: u64) -> String {
+        let session = serde_json::json!({ "id": id, "keepalive_timeout_seconds": keepalive });
+        frame(
+            "session_welcome",
+            "w",
+            serde_json::json!({ "session": session }),
+        )
+    }
+
+    fn subscription(status: &str) -> serde_json::Value {
+        serde_json::json!({ "id": "s", "type": "channel.raid", "status": status })
+    }
+
+    fn notification(id: &str) -> String {
+        let subscription = subscription("enabled");
+        frame(
+            "notification",
+            id,
+            serde_json::json!({ "subscription": subscription }),
+        )
+    }
+
+    /// serves the frames in order on each accepted connection, and keeps it open.
+    pub async fn serve(connections: Vec<Vec<String>>) -> url::Url {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            for frames in connections {
+                let (stream, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                    for f in frames {
+                        ws.send(WSMessage::Text(f)).await.unwrap();
+                    }
+                    while ws.next().await.is_some() {}
+                });
+            }
+        });
+        format!("ws://{}", addr).parse().unwrap()
+    }
+
+    fn message_id(event: SessionEvent) -> String {
+        match event {
+            SessionEvent::Notification { message_id, .. } => message_id,
+            e => panic!("unexpected {:?}", e),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_new_session_after_keepalive_timeout() {
+        let url = serve(vec![vec![welcome("a", 1)], vec![welcome("b", 10)]]).await;
+        let mut session = Session::connect_to(url).await.unwrap();
+        assert_eq!(
+            session.next().await.unwrap(),
+            SessionEvent::Welcome("a".into())
+        );
+        assert_eq!(
+            session.next().await.unwrap(),
+            SessionEvent::Welcome("b".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_migrate_to_reconnect_url() {
+        let next = serve(vec![vec![welcome("a", 10), notification("2")]]).await;
+        let reconnect = frame(
+            "session_reconnect",
+            "r",
+            serde_json::json!({ "session": { "id": "a", "reconnect_url": next.as_str() } }),
+        );
+        let url = serve(vec![vec![welcome("a", 10), reconnect, notification("1")]]).await;
+
+        let mut session = Session::connect_to(url).await.unwrap();
+        assert_eq!(
+            session.next().await.unwrap(),
+            SessionEvent::Welcome("a".into())
+        );
+        let mut ids = vec![
+            message_id(session.next().await.unwrap()),
+            message_id(session.next().await.unwrap()),
+        ];
+        ids.sort();
+        assert_eq!(ids, vec!["1", "2"]);
+    }
+
+    #[tokio::test]
+    async fn test_revocation() {
+        let subscription = subscription("authorization_revoked");
+        let revocation = frame(
+            "revocation",
+            "v",
+            serde_json::json!({ "subscription": subscription }),
+        );
+        let url = serve(vec![vec![welcome("a", 10), revocation]]).await;
+        let mut session = Session::connect_to(url).await.unwrap();
+        assert_eq!(
+            session.next().await.unwrap(),
+            SessionEvent::Welcome("a".into())
+        );
+        assert_eq!(
+            session.next().await.unwrap(),
+            SessionEvent::Revocation {
+                subscription_id: "s".into(),
+                subscription_type: "channel.raid".into(),
+                status: "authorization_revoked".into(),
+            }
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use miette::{miette, IntoDiagnostic, Result};
+use twitch_api::helix::HelixClient;
+use twitch_api::twitch_oauth2::{TwitchToken, UserToken};
+use twitch_api::types::{UserId, UserName};
+
+/// looks up the id of the user by the login name.
+pub async fn get_user_id_for<'a>(
+    client: &HelixClient<'a, reqwest::Client>,
+    token: &UserToken,
+    login: &UserName,
+) -> Result<UserId> {
+    client
+        .get_user_from_login(login, token)
+        .await
+        .into_diagnostic()?
+        .ok_or_else(|| miette!("No user found for {}.", login))
+        .map(|user| user.id)
+}
+
+/// posts a JSON body to a Helix endpoint which twitch_api does not support yet.
+/// `base` is the url of Helix, i.e. `twitch_api::TWITCH_HELIX_URL` except for the tests.
+pub async fn post_json(
+    http: &reqwest::Client,
+    base: &url::Url,
+    token: &UserToken,
+    path: &str,
+    body: &serde_json::Value,
+) -> Result<serde_json::Value> {
+    let url = base.join(path).into_diagnostic()?;
+    let res = http
+        .post(url)
+        .bearer_auth(token.token().secret())
+        .header("Client-Id", token.client_id().as_str())
+        .json(body)
+        .send()
+        .await
+        .into_diagnostic()?;
+    let status = res.status();
+    if !status.is_success() {
+        let text = res.text().await.unwrap_or_default();
+        return Err(miette!("helix {} failed ({}): {}", path, status, text));
+    }
+    res.json().await.into_diagnostic()
+}
+
+/// deletes a resource of a Helix endpoint which twitch_api does not support yet.
+pub async fn delete(
+    http: &reqwest::Client,
+    base: &url::Url,
+    token: &UserToken,
+    path: &str,
+) -> Result<()> {
+    let url = base.join(path).into_diagnostic()?;
+    let res = http
+        .delete(url)
+        .bearer_auth(token.token().secret())
+        .header("Client-Id", token.client_id().as_str())
+        .send()
+        .await
+        .into_diagnostic()?;
+    let status = res.status();
+    if !status.is_success() {
+        let text = res.text().await.unwrap_or_default();
+        return Err(miette!("helix {} failed ({}): {}", path, status, text));
+    }
+    Ok(())
+}
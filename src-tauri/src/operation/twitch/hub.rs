@@ -0,0 +1,654 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use miette::{miette, IntoDiagnostic, Result};
+use tokio::sync::{mpsc, oneshot};
+use twitch_api::eventsub::EventSubscription;
+
+use crate::model::error::MrDamianError;
+use crate::operation::twitch::{helix, SeenMessages, Session, SessionEvent, TokenProvider};
+
+const BACKOFF_MIN: Duration = Duration::from_secs(1);
+const BACKOFF_MAX: Duration = Duration::from_secs(60);
+/// how long the connection may take to be closed, before it is dropped.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Subscription is an EventSub subscription which a node wants, without the transport.
+#[derive(Debug, Clone)]
+pub struct Subscription {
+    pub kind: String,
+    pub version: String,
+    pub condition: serde_json::Value,
+}
+
+impl Subscription {
+    pub fn new(kind: &str, version: &str, condition: serde_json::Value) -> Self {
+        Self {
+            kind: kind.to_string(),
+            version: version.to_string(),
+            condition,
+        }
+    }
+
+    pub fn of<E: EventSubscription>(sub: &E) -> Result<Self> {
+        let kind = serde_json::to_value(E::EVENT_TYPE).into_diagnostic()?;
+        Ok(Self {
+            kind: kind.as_str().unwrap_or_default().to_string(),
+            version: E::VERSION.to_string(),
+            condition: sub.condition().into_diagnostic()?,
+        })
+    }
+}
+
+enum Command {
+    Register {
+        component: String,
+        registration: u64,
+        auth: TokenProvider,
+        subscriptions: Vec<Subscription>,
+        sender: mpsc::UnboundedSender<Result<SessionEvent>>,
+    },
+    Unregister {
+        component: String,
+        registration: u64,
+        /// notified when the subscriptions are deleted.
+        done: Option<oneshot::Sender<()>>,
+    },
+}
+
+/// ids of the notifications delivered to each node.
+type Seen = Arc<Mutex<HashMap<String, SeenMessages>>>;
+
+/// Urls are the endpoints of twitch, which the tests replace with stand-ins.
+#[derive(Debug, Clone)]
+struct Urls {
+    websocket: url::Url,
+    helix: url::Url,
+}
+
+impl Default for Urls {
+    fn default() -> Self {
+        Self {
+            websocket: twitch_api::TWITCH_EVENTSUB_WEBSOCKET_URL.clone(),
+            helix: twitch_api::TWITCH_HELIX_URL.clone(),
+        }
+    }
+}
+
+/// EventSubHub shares one EventSub websocket among the subscriber-type nodes of the pipeline,
+/// since twitch limits the connections and the subscriptions per user.
+/// the connection is opened while at least one node is registered.
+#[derive(Debug, Clone, Default)]
+pub struct EventSubHub {
+    commands: Arc<Mutex<Option<mpsc::UnboundedSender<Command>>>>,
+    registrations: Arc<AtomicU64>,
+    /// kept here rather than in the processes, so that a restarted process
+    /// does not take the redelivered notifications as new ones.
+    seen: Seen,
+    urls: Urls,
+}
+
+impl EventSubHub {
+    /// creates the subscriptions for the component, on the current and the later sessions.
+    /// registering the component again replaces the subscriptions.
+    pub fn register(
+        &self,
+        component: &str,
+        auth: TokenProvider,
+        subscriptions: Vec<Subscription>,
+    ) -> Subscriptions {
+        let registration = self.registrations.fetch_add(1, Ordering::Relaxed);
+        let (sender, events) = mpsc::unbounded_channel();
+        let commands = self.commands();
+        // the hub lives as long as the sender which is kept here.
+        let _ = commands.send(Command::Register {
+            component: component.to_string(),
+            registration,
+            auth,
+            subscriptions,
+            sender,
+        });
+        Subscriptions {
+            component: component.to_string(),
+            registration,
+            events,
+            commands,
+        }
+    }
+
+    /// the number of redelivered notifications dropped for each node so far.
+    pub fn duplicates(&self) -> HashMap<String, u64> {
+        let seen = self.seen.lock().expect("Failed to lock seen messages");
+        seen.iter()
+            .map(|(component, s)| (component.clone(), s.duplicates()))
+            .collect()
+    }
+
+    fn commands(&self) -> mpsc::UnboundedSender<Command> {
+        let mut commands = self.commands.lock().expect("Failed to lock eventsub hub");
+        if let Some(commands) = commands.as_ref() {
+            return commands.clone();
+        }
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let hub = Hub {
+            seen: self.seen.clone(),
+            urls: self.urls.clone(),
+            ..Default::default()
+        };
+        tokio::spawn(hub.run(receiver));
+        *commands = Some(sender.clone());
+        sender
+    }
+}
+
+/// Subscriptions receives the notifications for the subscriptions of a node.
+/// the subscriptions are deleted when this is dropped.
+pub struct Subscriptions {
+    component: String,
+    registration: u64,
+    events: mpsc::UnboundedReceiver<Result<SessionEvent>>,
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+impl Subscriptions {
+    /// returns the next notification, or an error if a subscription
+    /// could not be created or is revoked.
+    pub async fn next(&mut self) -> Result<SessionEvent> {
+        match self.events.recv().await {
+            Some(Ok(SessionEvent::Revocation {
+                subscription_type,
+                status,
+                ..
+            })) => Err(MrDamianError::SubscriptionRevoked(subscription_type, status).into()),
+            Some(res) => res,
+            None => Err(miette!("eventsub hub was stopped")),
+        }
+    }
+
+    /// deletes the subscriptions, and waits until it is done.
+    pub async fn close(&mut self) {
+        let (done, wait) = oneshot::channel();
+        self.unregister(Some(done));
+        let _ = wait.await;
+    }
+
+    fn unregister(&self, done: Option<oneshot::Sender<()>>) {
+        let _ = self.commands.send(Command::Unregister {
+            component: self.component.clone(),
+            registration: self.registration,
+            done,
+        });
+    }
+}
+
+impl Drop for Subscriptions {
+    fn drop(&mut self) {
+        // nothing is left to delete if it is already closed.
+        self.unregister(None);
+    }
+}
+
+struct Node {
+    registration: u64,
+    auth: TokenProvider,
+    subscriptions: Vec<Subscription>,
+    sender: mpsc::UnboundedSender<Result<SessionEvent>>,
+    /// ids of the subscriptions created on the current session.
+    created: Vec<String>,
+}
+
+/// Reader keeps the session, and reconnects with a backoff when it fails.
+struct Reader {
+    task: tokio::task::JoinHandle<()>,
+    events: mpsc::UnboundedReceiver<SessionEvent>,
+    stop: Option<oneshot::Sender<()>>,
+}
+
+impl Reader {
+    fn start(url: url::Url) -> Self {
+        let (sender, events) = mpsc::unbounded_channel();
+        let (stop, mut stopped) = oneshot::channel();
+        let task = tokio::spawn(async move {
+            let mut backoff = BACKOFF_MIN;
+            loop {
+                match Session::connect_to(url.clone()).await {
+                    Ok(mut session) => loop {
+                        let next = tokio::select! {
+                            next = session.next() => next,
+                            _ = &mut stopped => return session.close().await,
+                        };
+                        match next {
+                            Ok(event) => {
+                                backoff = BACKOFF_MIN;
+                                if sender.send(event).is_err() {
+                                    return;
+                                }
+                            }
+                            Err(err) => {
+                                tracing::warn!("eventsub session failed: {:?}", err);
+                                break;
+                            }
+                        }
+                    },
+                    Err(err) => tracing::warn!("failed to connect eventsub: {:?}", err),
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(backoff) => (),
+                    _ = &mut stopped => return,
+                }
+                backoff = (backoff * 2).min(BACKOFF_MAX);
+            }
+        });
+        Self {
+            task,
+            events,
+            stop: Some(stop),
+        }
+    }
+
+    /// closes the connection with a close frame.
+    async fn close(mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+        let _ = tokio::time::timeout(CLOSE_TIMEOUT, &mut self.task).await;
+    }
+}
+
+impl Drop for Reader {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[derive(Default)]
+struct Hub {
+    http: reqwest::Client,
+    nodes: HashMap<String, Node>,
+    /// component id by subscription id.
+    routes: HashMap<String, String>,
+    session_id: Option<String>,
+    reader: Option<Reader>,
+    seen: Seen,
+    urls: Urls,
+}
+
+impl Hub {
+    async fn run(mut self, mut commands: mpsc::UnboundedReceiver<Command>) {
+        loop {
+            tokio::select! {
+                command = commands.recv() => match command {
+                    Some(command) => self.command(command).await,
+                    None => return,
+                },
+                event = next_event(&mut self.reader) => match event {
+                    Some(event) => self.dispatch(event).await,
+                    None => self.reader = None,
+                },
+            }
+        }
+    }
+
+    async fn command(&mut self, command: Command) {
+        match command {
+            Command::Register {
+                component,
+                registration,
+                auth,
+                subscriptions,
+                sender,
+            } => {
+                self.remove(&component).await;
+                self.nodes.insert(
+                    component.clone(),
+                    Node {
+                        registration,
+                        auth,
+                        subscriptions,
+                        sender,
+                        created: vec![],
+                    },
+                );
+                if self.reader.is_none() {
+                    self.session_id = None;
+                    self.reader = Some(Reader::start(self.urls.websocket.clone()));
+                }
+                self.subscribe(&component).await;
+            }
+            Command::Unregister {
+                component,
+                registration,
+                done,
+            } => {
+                // the component may be registered again by a new process.
+                if self.nodes.get(&component).map(|n| n.registration) == Some(registration) {
+                    self.remove(&component).await;
+                    // nothing is redelivered for the deleted subscriptions.
+                    self.seen
+                        .lock()
+                        .expect("Failed to lock seen messages")
+                        .remove(&component);
+                    if self.nodes.is_empty() {
+                        self.session_id = None;
+                        if let Some(reader) = self.reader.take() {
+                            reader.close().await;
+                        }
+                    }
+                }
+                if let Some(done) = done {
+                    let _ = done.send(());
+                }
+            }
+        }
+    }
+
+    /// deletes the subscriptions of the component,
+    /// since disabled ones are left on twitch for a while and count against the limit.
+    async fn remove(&mut self, component: &str) {
+        let Some(node) = self.nodes.remove(component) else {
+            return;
+        };
+        self.routes.retain(|_, c| c != component);
+        if node.created.is_empty() {
+            return;
+        }
+        let token = match node.auth.token().await {
+            Ok(token) => token,
+            Err(err) => {
+                tracing::warn!(component, "failed to delete subscriptions: {:?}", err);
+                return;
+            }
+        };
+        for id in node.created {
+            let path = format!("eventsub/subscriptions?id={}", id);
+            if let Err(err) = helix::delete(&self.http, &self.urls.helix, &token, &path).await {
+                tracing::warn!(component, "failed to delete subscription {}: {:?}", id, err);
+            }
+        }
+    }
+
+    /// creates the subscriptions of the component on the current session.
+    /// the node gets the error if any of them cannot be created, and stops.
+    async fn subscribe(&mut self, component: &str) {
+        let (Some(session_id), Some(node)) = (&self.session_id, self.nodes.get_mut(component))
+        else {
+            return;
+        };
+        let token = match node.auth.token().await {
+            Ok(token) => token,
+            Err(err) => {
+                tracing::warn!(component, "failed to subscribe: {:?}", err);
+                let _ = node.sender.send(Err(err));
+                return;
+            }
+        };
+        for sub in &node.subscriptions {
+            let body = serde_json::json!({
+                "type": sub.kind,
+                "version": sub.version,
+                "condition": sub.condition,
+                "transport": {
+                    "method": "websocket",
+                    "session_id": session_id,
+                },
+            });
+            let path = "eventsub/subscriptions";
+            let created = helix::post_json(&self.http, &self.urls.helix, &token, path, &body)
+                .await
+                .and_then(|res| {
+                    res["data"][0]["id"]
+                        .as_str()
+                        .map(str::to_string)
+                        .ok_or_else(|| MrDamianError::MessageKeyNotFound.into())
+                });
+            match created {
+                Ok(id) => {
+                    self.routes.insert(id.clone(), component.to_string());
+                    node.created.push(id);
+                }
+                Err(err) => {
+                    tracing::warn!(component, "failed to subscribe {}: {:?}", sub.kind, err);
+                    let err = err.wrap_err(format!("failed to subscribe {}", sub.kind));
+                    let _ = node.sender.send(Err(err));
+                    return;
+                }
+            }
+        }
+    }
+
+    async fn dispatch(&mut self, event: SessionEvent) {
+        let subscription_id = match &event {
+            SessionEvent::Welcome(session_id) => {
+                // the subscriptions of the previous session are gone.
+                self.session_id = Some(session_id.clone());
+                self.routes.clear();
+                let components: Vec<String> = self.nodes.keys().cloned().collect();
+                for component in components {
+                    if let Some(node) = self.nodes.get_mut(&component) {
+                        node.created.clear();
+                    }
+                    self.subscribe(&component).await;
+                }
+                return;
+            }
+            SessionEvent::Notification {
+                subscription_id, ..
+            }
+            | SessionEvent::Revocation {
+                subscription_id, ..
+            } => subscription_id,
+        };
+        let Some((component, node)) = self
+            .routes
+            .get(subscription_id)
+            .and_then(|component| Some((component, self.nodes.get(component)?)))
+        else {
+            tracing::debug!("no node for subscription {}", subscription_id);
+            return;
+        };
+        // twitch may deliver a notification more than once.
+        if let SessionEvent::Notification { message_id, .. } = &event {
+            let mut seen = self.seen.lock().expect("Failed to lock seen messages");
+            let seen = seen.entry(component.clone()).or_default();
+            if !seen.insert(message_id) {
+                tracing::info!(
+                    component,
+                    duplicates = seen.duplicates(),
+                    "dropped the redelivered message {}",
+                    message_id
+                );
+                return;
+            }
+        }
+        let _ = node.sender.send(Ok(event));
+    }
+}
+
+async fn next_event(reader: &mut Option<Reader>) -> Option<SessionEvent> {
+    match reader {
+        Some(reader) => reader.events.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::operation::twitch::eventsub::test::{frame, serve, welcome};
+    use crate::operation::twitch::test_util::{self, VALIDATED};
+    use crate::operation::twitch::OAuth;
+
+    /// stands in for the OAuth2 and Helix endpoints.
+    /// a subscription is created with its type as the id, except for the type "fail".
+    async fn stand_in() -> (url::Url, test_util::Requests) {
+        test_util::stand_in(
+            |request| match (request.method.as_str(), request.path.as_str()) {
+                ("GET", "/oauth2/validate") => (200, VALIDATED.to_string()),
+                ("POST", "/helix/eventsub/subscriptions") => {
+                    match request.json()["type"].as_str() {
+                        Some("fail") => (400, r#"{"status":400,"message":"invalid"}"#.to_string()),
+                        kind => (
+                            202,
+                            serde_json::json!({ "data": [{ "id": kind }] }).to_string(),
+                        ),
+                    }
+                }
+                ("DELETE", path) if path.starts_with("/helix/eventsub/subscriptions") => {
+                    (204, String::new())
+                }
+                _ => panic!("unexpected {request:?}"),
+            },
+        )
+        .await
+    }
+
+    fn hub(base: &url::Url, websocket: url::Url) -> EventSubHub {
+        EventSubHub {
+            urls: Urls {
+                websocket,
+                helix: base.join("helix/").unwrap(),
+            },
+            ..Default::default()
+        }
+    }
+
+    fn auth(base: &url::Url) -> TokenProvider {
+        TokenProvider::from_access_token(OAuth::new(base.join("oauth2/").unwrap()), "token")
+    }
+
+    fn subscriptions(kind: &str) -> Vec<Subscription> {
+        vec![Subscription::new(kind, "1", serde_json::json!({}))]
+    }
+
+    /// a notification frame of the subscription created for the type.
+    fn notified(message_id: &str, kind: &str) -> String {
+        let subscription = serde_json::json!({ "id": kind, "type": kind, "status": "enabled" });
+        frame(
+            "notification",
+            message_id,
+            serde_json::json!({ "subscription": subscription }),
+        )
+    }
+
+    fn message_id(event: SessionEvent) -> String {
+        match event {
+            SessionEvent::Notification { message_id, .. } => message_id,
+            e => panic!("unexpected {:?}", e),
+        }
+    }
+
+    fn node(sender: mpsc::UnboundedSender<Result<SessionEvent>>) -> Node {
+        let oauth = OAuth::new("http://127.0.0.1:9/oauth2/".parse().unwrap());
+        Node {
+            registration: 0,
+            auth: TokenProvider::from_access_token(oauth, "token"),
+            subscriptions: vec![],
+            sender,
+            created: vec!["sub".to_string()],
+        }
+    }
+
+    fn notification(message_id: &str) -> SessionEvent {
+        SessionEvent::Notification {
+            message_id: message_id.to_string(),
+            subscription_id: "sub".to_string(),
+            subscription_type: "channel.raid".to_string(),
+            frame: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_drop_redelivered() {
+        let eventsub = EventSubHub::default();
+        let mut hub = Hub {
+            seen: eventsub.seen.clone(),
+            ..Default::default()
+        };
+        let (sender, mut events) = mpsc::unbounded_channel();
+        hub.nodes.insert("node".to_string(), node(sender));
+        hub.routes.insert("sub".to_string(), "node".to_string());
+
+        hub.dispatch(notification("a")).await;
+        hub.dispatch(notification("a")).await;
+        assert!(events.try_recv().is_ok());
+        assert!(events.try_recv().is_err());
+
+        // a restarted process does not get it again either.
+        let (sender, mut events) = mpsc::unbounded_channel();
+        hub.nodes.insert("node".to_string(), node(sender));
+        hub.dispatch(notification("a")).await;
+        hub.dispatch(notification("b")).await;
+        let Ok(Ok(SessionEvent::Notification { message_id, .. })) = events.try_recv() else {
+            panic!("the new notification is not delivered");
+        };
+        assert_eq!(message_id, "b");
+        assert!(events.try_recv().is_err());
+        assert_eq!(
+            eventsub.duplicates(),
+            HashMap::from([("node".to_string(), 2)])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_route() {
+        let (base, _) = stand_in().await;
+        let websocket = serve(vec![vec![
+            welcome("a", 10),
+            notified("1", "channel.raid"),
+            notified("2", "channel.follow"),
+        ]])
+        .await;
+        let eventsub = hub(&base, websocket);
+        let mut raid = eventsub.register("raid", auth(&base), subscriptions("channel.raid"));
+        let mut follow = eventsub.register("follow", auth(&base), subscriptions("channel.follow"));
+
+        assert_eq!(message_id(raid.next().await.unwrap()), "1");
+        assert_eq!(message_id(follow.next().await.unwrap()), "2");
+    }
+
+    #[tokio::test]
+    async fn test_unregister() {
+        let (base, requests) = stand_in().await;
+        let websocket = serve(vec![vec![welcome("a", 10), notified("1", "channel.raid")]]).await;
+        let eventsub = hub(&base, websocket);
+        let mut raid = eventsub.register("raid", auth(&base), subscriptions("channel.raid"));
+        let mut follow = eventsub.register("follow", auth(&base), subscriptions("channel.follow"));
+        // both are subscribed once the notification is delivered.
+        assert_eq!(message_id(raid.next().await.unwrap()), "1");
+
+        let deleted = |requests: &test_util::Requests| -> Vec<String> {
+            let requests = requests.lock().unwrap();
+            requests
+                .iter()
+                .filter(|r| r.method == "DELETE")
+                .map(|r| r.line())
+                .collect()
+        };
+        raid.close().await;
+        assert!(!eventsub.duplicates().contains_key("raid"));
+        assert_eq!(
+            deleted(&requests),
+            vec!["DELETE /helix/eventsub/subscriptions?id=channel.raid"]
+        );
+        follow.close().await;
+        assert_eq!(
+            deleted(&requests),
+            vec![
+                "DELETE /helix/eventsub/subscriptions?id=channel.raid",
+                "DELETE /helix/eventsub/subscriptions?id=channel.follow",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_to_subscribe() {
+        let (base, _) = stand_in().await;
+        let websocket = serve(vec![vec![welcome("a", 10)]]).await;
+        let eventsub = hub(&base, websocket);
+        let mut failed = eventsub.register("failed", auth(&base), subscriptions("fail"));
+
+        assert!(failed.next().await.is_err());
+    }
+}
@@ -0,0 +1,323 @@
+use async_trait::async_trait;
+use twitch_api::{
+    helix::{channels::ChannelInformation, users::User, HelixClient},
+    types::{UserId, UserName},
+};
+
+use miette::{miette, IntoDiagnostic, Result};
+
+use crate::{
+    model::error::MrDamianError,
+    model::{InputPort, OutputPort, Params, PropertyType},
+    operation::pipeline::{
+        Component, Connection, Constructor, Context, DefaultProcess, Message, Packet, Process,
+        ProcessInit, Property,
+    },
+    operation::twitch::TokenProvider,
+};
+
+const USER_KIND: &str = "TwitchLookupUser";
+const USER_LABEL: &str = "Lookup User";
+
+const USER_PROPERTIES: &[(&str, PropertyType)] = &[
+    ("user_id", PropertyType::Text),
+    ("user_login", PropertyType::Text),
+    ("user_name", PropertyType::Text),
+    ("description", PropertyType::Text),
+    ("profile_image_url", PropertyType::Text),
+    ("broadcaster_type", PropertyType::Text),
+    ("created_at", PropertyType::Timestamp),
+];
+
+const CHANNEL_KIND: &str = "TwitchLookupChannelInfo";
+const CHANNEL_LABEL: &str = "Lookup Channel Info";
+
+const CHANNEL_PROPERTIES: &[(&str, PropertyType)] = &[
+    ("broadcaster_id", PropertyType::Text),
+    ("broadcaster_login", PropertyType::Text),
+    ("broadcaster_name", PropertyType::Text),
+    ("broadcaster_language", PropertyType::Text),
+    ("game_id", PropertyType::Text),
+    ("game_name", PropertyType::Text),
+    ("title", PropertyType::Text),
+    ("tags", PropertyType::List),
+];
+
+fn text(msg: &Message, key: &str) -> Option<String> {
+    msg.get(key)
+        .map(Property::to_string)
+        .filter(|v| !v.is_empty())
+}
+
+fn message<const N: usize>(props: [(&str, Property); N]) -> Message {
+    props
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect()
+}
+
+fn user_message(user: User) -> Message {
+    message([
+        ("user_id", user.id.take().into()),
+        ("user_login", user.login.take().into()),
+        ("user_name", user.display_name.take().into()),
+        ("description", user.description.unwrap_or_default().into()),
+        (
+            "profile_image_url",
+            user.profile_image_url.unwrap_or_default().into(),
+        ),
+        (
+            "broadcaster_type",
+            user.broadcaster_type
+                .and_then(|t| serde_json::to_value(t).ok())
+                .and_then(|t| t.as_str().map(str::to_string))
+                .unwrap_or_default()
+                .into(),
+        ),
+        ("created_at", Property::Timestamp(user.created_at.take())),
+    ])
+}
+
+fn channel_message(channel: ChannelInformation) -> Message {
+    message([
+        ("broadcaster_id", channel.broadcaster_id.take().into()),
+        ("broadcaster_login", channel.broadcaster_login.take().into()),
+        ("broadcaster_name", channel.broadcaster_name.take().into()),
+        ("broadcaster_language", channel.broadcaster_language.into()),
+        ("game_id", channel.game_id.take().into()),
+        ("game_name", channel.game_name.take().into()),
+        ("title", channel.title.into()),
+        ("tags", channel.tags.into()),
+    ])
+}
+
+#[derive(Debug, Clone)]
+pub struct LookupUserComponent {
+    id: String,
+    auth: TokenProvider,
+}
+
+impl LookupUserComponent {
+    pub fn constructor() -> Constructor {
+        Constructor {
+            kind: USER_KIND,
+            label: USER_LABEL,
+            scopes: &[],
+            port_scopes: vec![],
+            params: vec![],
+            deprecated: false,
+            restart_on_outputs: false,
+            gen: Box::new(
+                |id: &str, _: &Params, ctx: &Context| -> Box<dyn Component + Send> {
+                    Box::new(LookupUserComponent::new(id, ctx.auth.clone()))
+                },
+            ),
+        }
+    }
+
+    pub fn new(id: &str, auth: TokenProvider) -> Self {
+        Self {
+            id: id.to_string(),
+            auth,
+        }
+    }
+}
+
+impl Component for LookupUserComponent {
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn kind(&self) -> &'static str {
+        USER_KIND
+    }
+
+    fn label(&self) -> &'static str {
+        USER_LABEL
+    }
+
+    fn inputs(&self) -> Vec<InputPort> {
+        vec![InputPort::new(
+            &self.id,
+            "user",
+            &[
+                ("user_id", PropertyType::Text),
+                ("user_login", PropertyType::Text),
+            ],
+        )
+        // either of them identifies the user.
+        .with_optional(&["user_id", "user_login"])]
+    }
+
+    fn outputs(&self) -> Vec<OutputPort> {
+        vec![OutputPort::new(&self.id, "user", USER_PROPERTIES)]
+    }
+
+    fn spawn(&self) -> ProcessInit {
+        Box::pin(LookupUserProcess::initializer(self.clone()))
+    }
+}
+
+pub struct LookupUserProcess {
+    client: HelixClient<'static, reqwest::Client>,
+    auth: TokenProvider,
+}
+
+impl LookupUserProcess {
+    pub async fn initializer(component: LookupUserComponent) -> Result<Box<dyn Process + Send>> {
+        Ok(Box::new(Self {
+            client: HelixClient::default(),
+            auth: component.auth,
+        }))
+    }
+}
+
+#[async_trait]
+impl Process for LookupUserProcess {
+    async fn run(&mut self, conn: &mut Connection) -> Result<()> {
+        self.default_run(conn).await
+    }
+}
+
+#[async_trait]
+impl DefaultProcess for LookupUserProcess {
+    async fn handler(&mut self, packet: Packet) -> Result<Vec<Packet>> {
+        if packet.port != "user" {
+            // drop all packets that are not from the user port.
+            return Ok(vec![]);
+        }
+
+        let msg = packet.message;
+        let token = self.auth.token().await?;
+
+        // the id is preferred since the login name can be changed by the user.
+        let user = if let Some(id) = text(&msg, "user_id") {
+            let id: UserId = id.into();
+            self.client.get_user_from_id(&id, &token).await
+        } else if let Some(login) = text(&msg, "user_login") {
+            let login: UserName = login.into();
+            self.client.get_user_from_login(&login, &token).await
+        } else {
+            return Err(MrDamianError::MessageKeyNotFound.into());
+        };
+        let user = user
+            .into_diagnostic()?
+            .ok_or_else(|| miette!("No user found for {:?}.", msg))?;
+
+        Ok(vec![Packet {
+            port: "user".to_string(),
+            message: user_message(user),
+        }])
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LookupChannelComponent {
+    id: String,
+    auth: TokenProvider,
+}
+
+impl LookupChannelComponent {
+    pub fn constructor() -> Constructor {
+        Constructor {
+            kind: CHANNEL_KIND,
+            label: CHANNEL_LABEL,
+            scopes: &[],
+            port_scopes: vec![],
+            params: vec![],
+            deprecated: false,
+            restart_on_outputs: false,
+            gen: Box::new(
+                |id: &str, _: &Params, ctx: &Context| -> Box<dyn Component + Send> {
+                    Box::new(LookupChannelComponent::new(id, ctx.auth.clone()))
+                },
+            ),
+        }
+    }
+
+    pub fn new(id: &str, auth: TokenProvider) -> Self {
+        Self {
+            id: id.to_string(),
+            auth,
+        }
+    }
+}
+
+impl Component for LookupChannelComponent {
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn kind(&self) -> &'static str {
+        CHANNEL_KIND
+    }
+
+    fn label(&self) -> &'static str {
+        CHANNEL_LABEL
+    }
+
+    fn inputs(&self) -> Vec<InputPort> {
+        vec![InputPort::new(
+            &self.id,
+            "channel",
+            &[("broadcaster_id", PropertyType::Text)],
+        )]
+    }
+
+    fn outputs(&self) -> Vec<OutputPort> {
+        vec![OutputPort::new(&self.id, "channel", CHANNEL_PROPERTIES)]
+    }
+
+    fn spawn(&self) -> ProcessInit {
+        Box::pin(LookupChannelProcess::initializer(self.clone()))
+    }
+}
+
+pub struct LookupChannelProcess {
+    client: HelixClient<'static, reqwest::Client>,
+    auth: TokenProvider,
+}
+
+impl LookupChannelProcess {
+    pub async fn initializer(component: LookupChannelComponent) -> Result<Box<dyn Process + Send>> {
+        Ok(Box::new(Self {
+            client: HelixClient::default(),
+            auth: component.auth,
+        }))
+    }
+}
+
+#[async_trait]
+impl Process for LookupChannelProcess {
+    async fn run(&mut self, conn: &mut Connection) -> Result<()> {
+        self.default_run(conn).await
+    }
+}
+
+#[async_trait]
+impl DefaultProcess for LookupChannelProcess {
+    async fn handler(&mut self, packet: Packet) -> Result<Vec<Packet>> {
+        if packet.port != "channel" {
+            // drop all packets that are not from the channel port.
+            return Ok(vec![]);
+        }
+
+        let Some(id) = text(&packet.message, "broadcaster_id") else {
+            return Err(MrDamianError::MessageKeyNotFound.into());
+        };
+        let id: UserId = id.into();
+
+        let token = self.auth.token().await?;
+        let channel = self
+            .client
+            .get_channel_from_id(&id, &token)
+            .await
+            .into_diagnostic()?
+            .ok_or_else(|| miette!("No channel info found for the user {}.", id))?;
+
+        Ok(vec![Packet {
+            port: "channel".to_string(),
+            message: channel_message(channel),
+        }])
+    }
+}
@@ -1,5 +1,27 @@
+pub mod announcement;
+pub mod auth;
+pub mod chat;
+pub mod event;
+pub mod eventsub;
+pub mod helix;
+pub mod hub;
+pub mod lookup;
 pub mod publisher;
+pub mod seen;
+pub mod send_chat;
+pub mod shoutout;
 pub mod subscriber;
+#[cfg(test)]
+mod test_util;
 
+pub use announcement::*;
+pub use auth::*;
+pub use chat::*;
+pub use eventsub::*;
+pub use hub::*;
+pub use lookup::*;
 pub use publisher::*;
+pub use seen::*;
+pub use send_chat::*;
+pub use shoutout::*;
 pub use subscriber::*;
@@ -1,54 +1,63 @@
 use async_trait::async_trait;
-
 use twitch_api::{
-    helix::HelixClient,
-    helix::{self, chat::AnnouncementColor},
-    twitch_oauth2::{AccessToken, UserToken},
-    types::{UserId, UserName},
+    helix::{self, chat::AnnouncementColor, HelixClient},
+    types::UserId,
 };
 
 use miette::{miette, IntoDiagnostic, Result};
 
 use crate::{
     model::error::MrDamianError,
-    model::{InputPort, InputPortID, OutputPort},
+    model::{InputPort, OutputPort, Params, PropertyType},
     operation::pipeline::{
-        Component, Connection, Constructor, DefaultProcess, Packet, Process, ProcessInit, Property,
+        Component, Connection, Constructor, Context, DefaultProcess, Packet, Process, ProcessInit,
     },
+    operation::twitch::{self, TokenProvider},
 };
 
+const KIND: &str = "TwitchPublisher";
+const LABEL: &str = "Twitch Publisher";
+
+/// PublisherComponent announces a raid and shouts the raider out, as one fixed flow.
+/// it is split into Lookup User, Lookup Channel Info, Send Announcement and Send Shoutout,
+/// and is kept only so that the pipelines saved before still run.
 #[derive(Debug, Clone)]
 pub struct PublisherComponent {
     id: String,
-    oauth: AccessToken,
-    channel: UserName,
-    bot: UserName,
+    auth: TokenProvider,
+    channel: String,
 }
 
 impl PublisherComponent {
     pub fn constructor() -> Constructor {
         Constructor {
-            kind: "TwitchPublisher",
-            label: "Twitch Publisher",
+            kind: KIND,
+            label: LABEL,
+            scopes: &[
+                "moderator:manage:announcements",
+                "moderator:manage:shoutouts",
+            ],
+            port_scopes: vec![],
+            params: vec![],
+            deprecated: true,
+            restart_on_outputs: false,
             gen: Box::new(
-                |id: &str, config: &crate::config::Config| -> Box<dyn Component + Send> {
+                |id: &str, _: &Params, ctx: &Context| -> Box<dyn Component + Send> {
                     Box::new(PublisherComponent::new(
                         id,
-                        &config.bot,
-                        &config.channel,
-                        &config.token,
+                        &ctx.config.channel,
+                        ctx.auth.clone(),
                     ))
                 },
             ),
         }
     }
 
-    pub fn new(id: &str, bot: &str, channel: &str, oauth: &str) -> Self {
+    pub fn new(id: &str, channel: &str, auth: TokenProvider) -> Self {
         Self {
             id: id.to_string(),
-            oauth: oauth.into(),
-            channel: channel.into(),
-            bot: bot.into(),
+            channel: channel.to_string(),
+            auth,
         }
     }
 }
@@ -59,25 +68,23 @@ impl Component for PublisherComponent {
     }
 
     fn kind(&self) -> &'static str {
-        "TwitchPublisher"
+        KIND
     }
 
     fn label(&self) -> &'static str {
-        "Twitch Publisher"
+        LABEL
     }
 
     fn inputs(&self) -> Vec<InputPort> {
-        vec![InputPort {
-            id: InputPortID {
-                parent: self.id.to_string(),
-                name: "message".to_string(),
-            },
-            property_names: vec![
-                "from_broadcaster_user_login".to_string(),
-                "from_broadcaster_user_id".to_string(),
-                "viewers".to_string(),
+        vec![InputPort::new(
+            &self.id,
+            "message",
+            &[
+                ("from_broadcaster_user_login", PropertyType::Text),
+                ("from_broadcaster_user_id", PropertyType::Text),
+                ("viewers", PropertyType::I64),
             ],
-        }]
+        )]
     }
 
     fn outputs(&self) -> Vec<OutputPort> {
@@ -91,65 +98,53 @@ impl Component for PublisherComponent {
 
 pub struct PublisherProcess {
     client: HelixClient<'static, reqwest::Client>,
-    token: UserToken,
+    auth: TokenProvider,
     channel_id: UserId,
-    bot_id: UserId,
 }
 
 impl PublisherProcess {
     pub async fn initializer(component: PublisherComponent) -> Result<Box<dyn Process + Send>> {
+        tracing::warn!(
+            component = component.id,
+            "{} is deprecated, replace it with Lookup User, Lookup Channel Info, Send Announcement and Send Shoutout",
+            LABEL
+        );
         let client: HelixClient<reqwest::Client> = HelixClient::default();
-
-        let token = UserToken::from_token(&client, component.oauth.clone())
-            .await
-            .into_diagnostic()?;
-
-        let channel_id = Self::get_user_id_for(&client, &token, &component.channel.clone()).await?;
-        let bot_id = Self::get_user_id_for(&client, &token, &component.bot.clone()).await?;
+        let token = component.auth.token().await?;
+        let channel_id =
+            twitch::helix::get_user_id_for(&client, &token, &component.channel.as_str().into())
+                .await?;
 
         Ok(Box::new(Self {
             client,
-            token,
+            auth: component.auth,
             channel_id,
-            bot_id,
         }))
     }
 
-    async fn get_user_id_for<'a>(
-        client: &HelixClient<'a, reqwest::Client>,
-        token: &UserToken,
-        name: &UserName,
-    ) -> Result<UserId> {
-        client
-            .get_user_from_login(name, token)
-            .await
-            .into_diagnostic()?
-            .ok_or_else(|| miette!("No user found for channel {channel}."))
-            .map(|user| user.id)
-    }
-
-    async fn send_shoutout(&mut self, to_broadcaster: &UserId) -> Result<()> {
+    async fn send_shoutout(&self, to_broadcaster: &UserId) -> Result<()> {
+        let token = self.auth.token().await?;
         let req = helix::chat::SendAShoutoutRequest::new(
             self.channel_id.clone(),
             to_broadcaster.clone(),
-            self.bot_id.clone(),
+            token.user_id.clone(),
         );
-
         self.client
-            .req_post(req, Default::default(), &self.token)
+            .req_post(req, Default::default(), &token)
             .await
             .into_diagnostic()?;
         Ok(())
     }
 
-    async fn send_notification(&mut self, message: &str) -> Result<()> {
+    async fn send_notification(&self, message: &str) -> Result<()> {
+        let token = self.auth.token().await?;
         self.client
             .send_chat_announcement(
                 self.channel_id.as_str(),
-                self.bot_id.as_str(),
+                token.user_id.as_str(),
                 message,
                 AnnouncementColor::Primary,
-                &self.token,
+                &token,
             )
             .await
             .into_diagnostic()?;
@@ -173,29 +168,33 @@ impl DefaultProcess for PublisherProcess {
         }
 
         let msg = packet.message;
-
-        // TODO: allow users to customize the message via text formating component.
-        let Some(Property::Text(flogin)) = msg.get("from_broadcaster_user_login") else {
+        let Some(flogin) = msg
+            .get("from_broadcaster_user_login")
+            .and_then(|p| p.as_text())
+        else {
             return Err(MrDamianError::MessageKeyNotFound.into());
         };
-        let Some(Property::Text(fid)) = msg.get("from_broadcaster_user_id") else {
+        let Some(fid) = msg
+            .get("from_broadcaster_user_id")
+            .and_then(|p| p.as_text())
+        else {
             return Err(MrDamianError::MessageKeyNotFound.into());
         };
-        let Some(Property::I64(viewers)) = msg.get("viewers") else {
+        let Some(viewers) = msg.get("viewers").and_then(|p| p.as_i64()) else {
             return Err(MrDamianError::MessageKeyNotFound.into());
         };
-        let fid: UserId = fid.as_str().into();
+        let fid: UserId = fid.into();
 
+        let token = self.auth.token().await?;
         let user = self
             .client
-            .get_user_from_id(&fid, &self.token)
+            .get_user_from_id(&fid, &token)
             .await
             .into_diagnostic()?
             .ok_or_else(|| miette!("No user found for channel {}.", flogin))?;
-
         let channel = self
             .client
-            .get_channel_from_id(&fid, &self.token)
+            .get_channel_from_id(&fid, &token)
             .await
             .into_diagnostic()?
             .ok_or_else(|| miette!("No channel info found for the user {}.", flogin))?;
@@ -0,0 +1,95 @@
+use std::collections::{HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+/// how long the ids are remembered. twitch redelivers a notification within minutes.
+const WINDOW: Duration = Duration::from_secs(10 * 60);
+const CAPACITY: usize = 1000;
+
+/// SeenMessages remembers the ids of the recent EventSub messages,
+/// since twitch may deliver a notification more than once.
+/// ids are forgotten after the window, or when more than the capacity are kept.
+#[derive(Debug)]
+pub struct SeenMessages {
+    window: Duration,
+    capacity: usize,
+    order: VecDeque<(Instant, String)>,
+    ids: HashSet<String>,
+    duplicates: u64,
+}
+
+impl SeenMessages {
+    pub fn new(window: Duration, capacity: usize) -> Self {
+        Self {
+            window,
+            capacity,
+            order: VecDeque::new(),
+            ids: HashSet::new(),
+            duplicates: 0,
+        }
+    }
+
+    /// remembers the id, and returns false if it is already seen.
+    pub fn insert(&mut self, id: &str) -> bool {
+        self.insert_at(id, Instant::now())
+    }
+
+    fn insert_at(&mut self, id: &str, now: Instant) -> bool {
+        while let Some((at, old)) = self.order.front() {
+            if now.duration_since(*at) <= self.window {
+                break;
+            }
+            self.ids.remove(old);
+            self.order.pop_front();
+        }
+
+        if self.ids.contains(id) {
+            self.duplicates += 1;
+            return false;
+        }
+
+        if self.order.len() >= self.capacity {
+            if let Some((_, old)) = self.order.pop_front() {
+                self.ids.remove(&old);
+            }
+        }
+        self.ids.insert(id.to_string());
+        self.order.push_back((now, id.to_string()));
+        true
+    }
+
+    /// the number of messages dropped as duplicates so far.
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates
+    }
+}
+
+impl Default for SeenMessages {
+    fn default() -> Self {
+        Self::new(WINDOW, CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_seen_messages() {
+        let mut seen = SeenMessages::new(Duration::from_secs(10), 2);
+        let start = Instant::now();
+        assert!(seen.insert_at("a", start));
+        assert!(!seen.insert_at("a", start + Duration::from_secs(5)));
+        assert_eq!(seen.duplicates(), 1);
+
+        // forgotten after the window.
+        assert!(seen.insert_at("a", start + Duration::from_secs(11)));
+
+        // forgotten over the capacity.
+        let now = start + Duration::from_secs(12);
+        assert!(seen.insert_at("b", now));
+        assert!(seen.insert_at("c", now));
+        assert!(seen.insert_at("a", now));
+        assert!(!seen.insert_at("c", now));
+        assert_eq!(seen.duplicates(), 2);
+    }
+}
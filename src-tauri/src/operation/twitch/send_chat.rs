@@ -0,0 +1,374 @@
+use async_trait::async_trait;
+use std::time::Duration;
+use tokio::time::Instant;
+use twitch_api::{helix::HelixClient, types::UserId};
+
+use miette::{Result, WrapErr};
+
+use crate::{
+    model::error::MrDamianError,
+    model::{InputPort, OutputPort, ParamSpec, ParamType, ParamValue, Params, PropertyType},
+    operation::pipeline::{
+        Component, Connection, Constructor, Context, DefaultProcess, Monitor, Packet, Process,
+        ProcessInit, Property,
+    },
+    operation::twitch::{helix, TokenProvider},
+};
+
+const KIND: &str = "TwitchSendChatMessage";
+const LABEL: &str = "Send Chat Message";
+
+#[derive(Debug, Clone)]
+pub struct SendChatComponent {
+    id: String,
+    auth: TokenProvider,
+    monitor: Monitor,
+    channel: String,
+    cooldown: Duration,
+}
+
+impl SendChatComponent {
+    pub fn constructor() -> Constructor {
+        Constructor {
+            kind: KIND,
+            label: LABEL,
+            scopes: &["user:write:chat"],
+            port_scopes: vec![],
+            params: vec![
+                ParamSpec::new(
+                    "channel",
+                    "Channel (empty for the configured one)",
+                    ParamType::Text,
+                    ParamValue::Text(String::new()),
+                ),
+                ParamSpec::new(
+                    "cooldown",
+                    "Cooldown seconds",
+                    ParamType::Integer,
+                    ParamValue::Integer(0),
+                ),
+            ],
+            deprecated: false,
+            restart_on_outputs: false,
+            gen: Box::new(
+                |id: &str, params: &Params, ctx: &Context| -> Box<dyn Component + Send> {
+                    let channel = params
+                        .get("channel")
+                        .and_then(ParamValue::as_text)
+                        .filter(|c| !c.is_empty())
+                        .unwrap_or(&ctx.config.channel);
+                    let cooldown = params
+                        .get("cooldown")
+                        .and_then(ParamValue::as_i64)
+                        .unwrap_or_default();
+                    Box::new(SendChatComponent::new(
+                        id,
+                        channel,
+                        Duration::from_secs(cooldown.max(0) as u64),
+                        ctx.auth.clone(),
+                        ctx.monitor.clone(),
+                    ))
+                },
+            ),
+        }
+    }
+
+    pub fn new(
+        id: &str,
+        channel: &str,
+        cooldown: Duration,
+        auth: TokenProvider,
+        monitor: Monitor,
+    ) -> Self {
+        Self {
+            id: id.to_string(),
+            channel: channel.to_string(),
+            cooldown,
+            auth,
+            monitor,
+        }
+    }
+}
+
+impl Component for SendChatComponent {
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn kind(&self) -> &'static str {
+        KIND
+    }
+
+    fn label(&self) -> &'static str {
+        LABEL
+    }
+
+    fn inputs(&self) -> Vec<InputPort> {
+        vec![InputPort::new(
+            &self.id,
+            "message",
+            &[
+                ("text", PropertyType::Text),
+                ("reply_parent_message_id", PropertyType::Text),
+            ],
+        )
+        .with_optional(&["reply_parent_message_id"])]
+    }
+
+    fn outputs(&self) -> Vec<OutputPort> {
+        vec![]
+    }
+
+    fn spawn(&self) -> ProcessInit {
+        Box::pin(SendChatProcess::initializer(self.clone()))
+    }
+}
+
+pub struct SendChatProcess {
+    id: String,
+    http: reqwest::Client,
+    /// the url of Helix, which the tests replace with a stand-in.
+    helix: url::Url,
+    auth: TokenProvider,
+    monitor: Monitor,
+    channel_id: UserId,
+    cooldown: Duration,
+    last_sent: Option<Instant>,
+}
+
+impl SendChatProcess {
+    pub async fn initializer(component: SendChatComponent) -> Result<Box<dyn Process + Send>> {
+        let client: HelixClient<reqwest::Client> = HelixClient::default();
+        let token = component.auth.token().await?;
+        let channel_id =
+            helix::get_user_id_for(&client, &token, &component.channel.as_str().into()).await?;
+
+        Ok(Box::new(Self {
+            id: component.id,
+            http: reqwest::Client::new(),
+            helix: twitch_api::TWITCH_HELIX_URL.clone(),
+            auth: component.auth,
+            monitor: component.monitor,
+            channel_id,
+            cooldown: component.cooldown,
+            last_sent: None,
+        }))
+    }
+
+    /// sends the message. a message which twitch drops, e.g. by AutoMod, is reported
+    /// instead of failing the process, since any chatter can cause it.
+    async fn send(&self, text: &str, reply_to: Option<&str>) -> Result<()> {
+        let token = self.auth.token().await?;
+        let mut body = serde_json::json!({
+            "broadcaster_id": self.channel_id.as_str(),
+            "sender_id": token.user_id.as_str(),
+            "message": text,
+        });
+        if let Some(reply_to) = reply_to.filter(|r| !r.is_empty()) {
+            body["reply_parent_message_id"] = reply_to.into();
+        }
+
+        let res = helix::post_json(&self.http, &self.helix, &token, "chat/messages", &body)
+            .await
+            .wrap_err("failed to send chat message")?;
+
+        // twitch answers 200 even if the message was dropped, e.g. by AutoMod.
+        let sent = &res["data"][0];
+        if sent["is_sent"] == false {
+            let reason = sent["drop_reason"]["message"].as_str().unwrap_or_default();
+            let reason = format!("the message was dropped by twitch: {}", reason);
+            self.monitor.discard(&self.id, &reason);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Process for SendChatProcess {
+    async fn run(&mut self, conn: &mut Connection) -> Result<()> {
+        self.default_run(conn).await
+    }
+}
+
+#[async_trait]
+impl DefaultProcess for SendChatProcess {
+    async fn handler(&mut self, packet: Packet) -> Result<Vec<Packet>> {
+        if packet.port != "message" {
+            // drop all packets that are not from the message port.
+            return Ok(vec![]);
+        }
+
+        if let Some(last) = self.last_sent {
+            if last.elapsed() < self.cooldown {
+                // drop messages within the cooldown to avoid flooding the chat.
+                let reason = format!("within the cooldown of {:?}", self.cooldown);
+                self.monitor.discard(&self.id, &reason);
+                return Ok(vec![]);
+            }
+        }
+
+        let msg = packet.message;
+        let Some(text) = msg.get("text").map(Property::to_string) else {
+            return Err(MrDamianError::MessageKeyNotFound.into());
+        };
+        let reply_to = msg
+            .get("reply_parent_message_id")
+            .and_then(Property::as_text);
+
+        self.send(&text, reply_to).await?;
+        // a failed message does not start the cooldown.
+        self.last_sent = Some(Instant::now());
+        Ok(vec![])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::DroppedPacket;
+    use crate::operation::pipeline::{Message, MonitorEvent};
+    use crate::operation::twitch::test_util::{self, VALIDATED};
+    use crate::operation::twitch::OAuth;
+
+    const SENT: &str = r#"{"data":[{"message_id":"m","is_sent":true}]}"#;
+    const HELD: &str = r#"{"data":[{"message_id":"","is_sent":false,"drop_reason":{"code":"msg_rejected","message":"held by AutoMod"}}]}"#;
+
+    /// stands in for twitch, which answers `sent` to the messages.
+    async fn stand_in(sent: &'static str) -> (url::Url, test_util::Requests) {
+        test_util::stand_in(move |request| match request.line().as_str() {
+            "GET /oauth2/validate" => (200, VALIDATED.to_string()),
+            "POST /helix/chat/messages" => (200, sent.to_string()),
+            _ => panic!("unexpected {request:?}"),
+        })
+        .await
+    }
+
+    fn process(base: &url::Url, monitor: &Monitor) -> SendChatProcess {
+        let oauth = OAuth::new(base.join("oauth2/").unwrap());
+        SendChatProcess {
+            id: "send".to_string(),
+            http: reqwest::Client::new(),
+            helix: base.join("helix/").unwrap(),
+            auth: TokenProvider::from_access_token(oauth, "token"),
+            monitor: monitor.clone(),
+            channel_id: "1".into(),
+            cooldown: Duration::ZERO,
+            last_sent: None,
+        }
+    }
+
+    fn packet(properties: &[(&str, &str)]) -> Packet {
+        let message: Message = properties
+            .iter()
+            .map(|(k, v)| (k.to_string(), Property::from(*v)))
+            .collect();
+        Packet {
+            port: "message".to_string(),
+            message,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reply() {
+        let (base, requests) = stand_in(SENT).await;
+        let mut process = process(&base, &Monitor::default());
+        process
+            .handler(packet(&[("text", "hi"), ("reply_parent_message_id", "p")]))
+            .await
+            .unwrap();
+        process.handler(packet(&[("text", "hello")])).await.unwrap();
+
+        let bodies: Vec<serde_json::Value> = requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.path == "/helix/chat/messages")
+            .map(test_util::Request::json)
+            .collect();
+        assert_eq!(
+            bodies,
+            vec![
+                serde_json::json!({
+                    "broadcaster_id": "1",
+                    "sender_id": "42",
+                    "message": "hi",
+                    "reply_parent_message_id": "p",
+                }),
+                serde_json::json!({
+                    "broadcaster_id": "1",
+                    "sender_id": "42",
+                    "message": "hello",
+                }),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dropped() {
+        let (base, _) = stand_in(HELD).await;
+        let monitor = Monitor::default();
+        let mut events = monitor.subscribe();
+        let mut process = process(&base, &monitor);
+
+        // the process keeps running.
+        process.handler(packet(&[("text", "hi")])).await.unwrap();
+        let Ok(MonitorEvent::Dropped(DroppedPacket::Discarded { component, reason })) =
+            events.try_recv()
+        else {
+            panic!("the dropped message is not reported");
+        };
+        assert_eq!(component, "send");
+        assert!(reason.contains("held by AutoMod"), "{}", reason);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cooldown() {
+        let (base, requests) = stand_in(SENT).await;
+        let monitor = Monitor::default();
+        let mut events = monitor.subscribe();
+        let mut process = SendChatProcess {
+            cooldown: Duration::from_secs(30),
+            ..process(&base, &monitor)
+        };
+        let sent = |requests: &test_util::Requests| {
+            let requests = requests.lock().unwrap();
+            requests
+                .iter()
+                .filter(|r| r.path == "/helix/chat/messages")
+                .count()
+        };
+
+        process.handler(packet(&[("text", "1")])).await.unwrap();
+        tokio::time::advance(Duration::from_secs(10)).await;
+        process.handler(packet(&[("text", "2")])).await.unwrap();
+        assert_eq!(sent(&requests), 1);
+        assert!(matches!(
+            events.try_recv(),
+            Ok(MonitorEvent::Dropped(DroppedPacket::Discarded { .. }))
+        ));
+
+        tokio::time::advance(Duration::from_secs(20)).await;
+        process.handler(packet(&[("text", "3")])).await.unwrap();
+        assert_eq!(sent(&requests), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cooldown_after_failure() {
+        let (base, requests) = test_util::stand_in(|request| match request.line().as_str() {
+            "GET /oauth2/validate" => (200, VALIDATED.to_string()),
+            _ => (500, String::new()),
+        })
+        .await;
+        let mut process = SendChatProcess {
+            cooldown: Duration::from_secs(30),
+            ..process(&base, &Monitor::default())
+        };
+
+        assert!(process.handler(packet(&[("text", "1")])).await.is_err());
+        // the failed one does not hold the next one back.
+        assert!(process.handler(packet(&[("text", "2")])).await.is_err());
+        let requests = requests.lock().unwrap();
+        let posts = requests.iter().filter(|r| r.method == "POST").count();
+        assert_eq!(posts, 2);
+    }
+}
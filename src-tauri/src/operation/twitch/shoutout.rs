@@ -0,0 +1,152 @@
+use async_trait::async_trait;
+use twitch_api::{
+    helix::{self, HelixClient},
+    types::UserId,
+};
+
+use miette::{IntoDiagnostic, Result};
+
+use crate::{
+    model::error::MrDamianError,
+    model::{InputPort, OutputPort, Params, PropertyType},
+    operation::pipeline::{
+        Component, Connection, Constructor, Context, DefaultProcess, Packet, Process, ProcessInit,
+        Property,
+    },
+    operation::twitch::{self, TokenProvider},
+};
+
+const KIND: &str = "TwitchShoutout";
+const LABEL: &str = "Send Shoutout";
+
+#[derive(Debug, Clone)]
+pub struct ShoutoutComponent {
+    id: String,
+    auth: TokenProvider,
+    channel: String,
+}
+
+impl ShoutoutComponent {
+    pub fn constructor() -> Constructor {
+        Constructor {
+            kind: KIND,
+            label: LABEL,
+            scopes: &["moderator:manage:shoutouts"],
+            port_scopes: vec![],
+            params: vec![],
+            deprecated: false,
+            restart_on_outputs: false,
+            gen: Box::new(
+                |id: &str, _: &Params, ctx: &Context| -> Box<dyn Component + Send> {
+                    Box::new(ShoutoutComponent::new(
+                        id,
+                        &ctx.config.channel,
+                        ctx.auth.clone(),
+                    ))
+                },
+            ),
+        }
+    }
+
+    pub fn new(id: &str, channel: &str, auth: TokenProvider) -> Self {
+        Self {
+            id: id.to_string(),
+            channel: channel.to_string(),
+            auth,
+        }
+    }
+}
+
+impl Component for ShoutoutComponent {
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn kind(&self) -> &'static str {
+        KIND
+    }
+
+    fn label(&self) -> &'static str {
+        LABEL
+    }
+
+    fn inputs(&self) -> Vec<InputPort> {
+        vec![InputPort::new(
+            &self.id,
+            "message",
+            &[("to_broadcaster_id", PropertyType::Text)],
+        )]
+    }
+
+    fn outputs(&self) -> Vec<OutputPort> {
+        vec![]
+    }
+
+    fn spawn(&self) -> ProcessInit {
+        Box::pin(ShoutoutProcess::initializer(self.clone()))
+    }
+}
+
+pub struct ShoutoutProcess {
+    client: HelixClient<'static, reqwest::Client>,
+    auth: TokenProvider,
+    channel_id: UserId,
+}
+
+impl ShoutoutProcess {
+    pub async fn initializer(component: ShoutoutComponent) -> Result<Box<dyn Process + Send>> {
+        let client: HelixClient<reqwest::Client> = HelixClient::default();
+        let token = component.auth.token().await?;
+        let channel_id =
+            twitch::helix::get_user_id_for(&client, &token, &component.channel.as_str().into())
+                .await?;
+
+        Ok(Box::new(Self {
+            client,
+            auth: component.auth,
+            channel_id,
+        }))
+    }
+
+    async fn send_shoutout(&self, to_broadcaster: UserId) -> Result<()> {
+        let token = self.auth.token().await?;
+        let req = helix::chat::SendAShoutoutRequest::new(
+            self.channel_id.clone(),
+            to_broadcaster,
+            token.user_id.clone(),
+        );
+        self.client
+            .req_post(req, Default::default(), &token)
+            .await
+            .into_diagnostic()?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Process for ShoutoutProcess {
+    async fn run(&mut self, conn: &mut Connection) -> Result<()> {
+        self.default_run(conn).await
+    }
+}
+
+#[async_trait]
+impl DefaultProcess for ShoutoutProcess {
+    async fn handler(&mut self, packet: Packet) -> Result<Vec<Packet>> {
+        if packet.port != "message" {
+            // drop all packets that are not from the message port.
+            return Ok(vec![]);
+        }
+
+        let Some(to) = packet
+            .message
+            .get("to_broadcaster_id")
+            .map(Property::to_string)
+        else {
+            return Err(MrDamianError::MessageKeyNotFound.into());
+        };
+
+        self.send_shoutout(to.into()).await?;
+        Ok(vec![])
+    }
+}
@@ -1,33 +1,29 @@
-use futures::StreamExt;
 use twitch_api::{
-    eventsub::{
-        Event, EventsubWebsocketData, Message as TwitchMessage, NotificationMetadata, Payload,
-        ReconnectPayload, WelcomePayload,
-    },
+    eventsub::{Event, EventsubWebsocketData},
     helix::HelixClient,
-    twitch_oauth2::{AccessToken, UserToken},
     types::{UserId, UserName},
 };
 
 use async_trait::async_trait;
-use miette::{miette, IntoDiagnostic, Result, WrapErr};
+use miette::{miette, IntoDiagnostic, Result};
 
 use crate::operation::pipeline::{
-    Component, Connection, Constructor, Message, Packet, Process, ProcessInit, Property,
+    Component, Connection, Constructor, Context, Packet, Process, ProcessInit,
+};
+use crate::operation::twitch::{
+    event, helix, EventSubHub, SessionEvent, Subscriptions, TokenProvider,
 };
 use crate::{
-    model::{InputPort, OutputPort, OutputPortID},
+    model::{InputPort, OutputPort, Params},
     operation::pipeline::PassiveProcess,
 };
 
-type WSConnection =
-    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
-
 #[derive(Debug, Clone)]
 pub struct SubscriberComponent {
     id: String,
-    oauth: AccessToken,
+    auth: TokenProvider,
     channel: UserName,
+    hub: EventSubHub,
 }
 
 impl SubscriberComponent {
@@ -35,19 +31,30 @@ impl SubscriberComponent {
         Constructor {
             kind: "TwitchSubscriber",
             label: "Twitch Subscriber",
+            scopes: &[],
+            port_scopes: event::EVENTS.iter().map(|e| (e.name, e.scopes)).collect(),
+            params: vec![],
+            deprecated: false,
+            restart_on_outputs: true,
             gen: Box::new(
-                |id: &str, config: &crate::config::Config| -> Box<dyn Component + Send> {
-                    Box::new(SubscriberComponent::new(id, &config.channel, &config.token))
+                |id: &str, _: &Params, ctx: &Context| -> Box<dyn Component + Send> {
+                    Box::new(SubscriberComponent::new(
+                        id,
+                        &ctx.config.channel,
+                        ctx.auth.clone(),
+                        ctx.eventsub.clone(),
+                    ))
                 },
             ),
         }
     }
 
-    pub fn new(id: &str, channel: &str, oauth: &str) -> Self {
+    pub fn new(id: &str, channel: &str, auth: TokenProvider, hub: EventSubHub) -> Self {
         Self {
             id: id.to_string(),
             channel: channel.into(),
-            oauth: oauth.into(),
+            auth,
+            hub,
         }
     }
 }
@@ -70,21 +77,10 @@ impl Component for SubscriberComponent {
     }
 
     fn outputs(&self) -> Vec<OutputPort> {
-        vec![OutputPort {
-            id: OutputPortID {
-                parent: self.id.clone(),
-                name: "raid".to_string(),
-            },
-            property_names: vec![
-                "from_broadcaster_user_id".to_string(),
-                "from_broadcaster_user_login".to_string(),
-                "from_broadcaster_user_name".to_string(),
-                "to_broadcaster_user_id".to_string(),
-                "to_broadcaster_user_login".to_string(),
-                "to_broadcaster_user_name".to_string(),
-                "viewers".to_string(),
-            ],
-        }]
+        event::EVENTS
+            .iter()
+            .map(|e| OutputPort::new(&self.id, e.name, e.properties))
+            .collect()
     }
 
     fn spawn(&self) -> ProcessInit {
@@ -93,143 +89,43 @@ impl Component for SubscriberComponent {
 }
 
 pub struct SubscriberProcess {
-    client: HelixClient<'static, reqwest::Client>,
-    token: UserToken,
+    component: SubscriberComponent,
     channel_id: UserId,
-    socket: WSConnection,
-    session_id: Option<String>,
-    reconnect_url: Option<url::Url>,
+    /// subscriptions of the connected output ports, registered when the process runs.
+    subscriptions: Option<Subscriptions>,
 }
 
 impl SubscriberProcess {
     async fn initializer(component: SubscriberComponent) -> Result<Box<dyn Process + Send>> {
         let client: HelixClient<reqwest::Client> = HelixClient::default();
-        let token = UserToken::from_token(&client, component.oauth.clone())
-            .await
-            .into_diagnostic()?;
+        let token = component.auth.token().await?;
 
-        let channel_id = Self::get_user_id_for(&client, &token, &component.channel.clone()).await?;
-        let socket = Self::connect().await?;
+        let channel_id =
+            helix::get_user_id_for(&client, &token, &component.channel.clone()).await?;
 
         Ok(Box::new(Self {
-            client,
-            token,
+            component,
             channel_id,
-            socket,
-            session_id: None,
-            reconnect_url: None,
+            subscriptions: None,
         }))
     }
 
-    pub async fn get_user_id_for<'a>(
-        client: &HelixClient<'a, reqwest::Client>,
-        token: &UserToken,
-        name: &UserName,
-    ) -> Result<UserId> {
-        client
-            .get_user_from_login(name, token)
-            .await
-            .into_diagnostic()?
-            .ok_or_else(|| miette!("No user found for channel {channel}."))
-            .map(|user| user.id)
-    }
-
-    async fn connect() -> Result<WSConnection> {
-        let config = tokio_tungstenite::tungstenite::protocol::WebSocketConfig::default();
-
-        let (socket, _) = tokio_tungstenite::connect_async_with_config(
-            twitch_api::TWITCH_EVENTSUB_WEBSOCKET_URL.clone(),
-            Some(config),
-        )
-        .await
-        .into_diagnostic()
-        .wrap_err("Cannot connect twitch event server host")?;
-
-        Ok(socket)
-    }
-
-    async fn process_message(&mut self, msg: String) -> Result<Vec<Packet>> {
-        use EventsubWebsocketData::*;
-        match Event::parse_websocket(msg.as_str()).into_diagnostic()? {
-            Welcome {
-                payload: WelcomePayload { session },
-                ..
-            }
-            | Reconnect {
-                payload: ReconnectPayload { session },
-                ..
-            } => {
-                self.session_id = Some(session.id.to_string());
-                if let Some(url) = session.reconnect_url {
-                    self.reconnect_url = Some(url.parse().into_diagnostic()?);
-                }
-
-                let req = twitch_api::helix::eventsub::CreateEventSubSubscriptionRequest::default();
-                let body = twitch_api::helix::eventsub::CreateEventSubSubscriptionBody::new(
-                    twitch_api::eventsub::channel::ChannelRaidV1::to_broadcaster_user_id(
-                        self.channel_id.clone(),
-                    ),
-                    twitch_api::eventsub::Transport::websocket(session.id.to_string()),
-                );
-
-                self.client
-                    .req_post(req, body, &self.token)
-                    .await
-                    .into_diagnostic()?;
-            }
-            Notification { metadata, payload } => {
-                return self.process_notification(&metadata, &payload).await;
+    async fn subscribe(&mut self, ports: &[String]) -> Result<()> {
+        let token = self.component.auth.token().await?;
+        let mut subscriptions = vec![];
+        for port in ports {
+            if let Some(sub) = event::subscription(&self.channel_id, &token.user_id, port)? {
+                subscriptions.push(sub);
             }
-            Revocation { .. } => (),
-            Keepalive { .. } => (),
-            _ => (),
         }
-
-        Ok(vec![])
+        let c = &self.component;
+        self.subscriptions = Some(c.hub.register(&c.id, c.auth.clone(), subscriptions));
+        Ok(())
     }
 
-    async fn process_notification<'a>(
-        &mut self,
-        _metadata: &NotificationMetadata<'a>,
-        payload: &Event,
-    ) -> Result<Vec<Packet>> {
-        match payload {
-            Event::ChannelRaidV1(Payload {
-                message: TwitchMessage::Notification(msg),
-                ..
-            }) => {
-                let mut message = Message::new();
-                message.insert("event".to_string(), Property::Text("raid".to_string()));
-                message.insert(
-                    "from_broadcaster_user_id".to_string(),
-                    Property::Text(msg.from_broadcaster_user_id.to_string()),
-                );
-                message.insert(
-                    "from_broadcaster_user_login".to_string(),
-                    Property::Text(msg.from_broadcaster_user_login.to_string()),
-                );
-                message.insert(
-                    "from_broadcaster_user_name".to_string(),
-                    Property::Text(msg.from_broadcaster_user_name.to_string()),
-                );
-                message.insert(
-                    "to_broadcaster_user_id".to_string(),
-                    Property::Text(msg.to_broadcaster_user_id.to_string()),
-                );
-                message.insert(
-                    "to_broadcaster_user_login".to_string(),
-                    Property::Text(msg.to_broadcaster_user_login.to_string()),
-                );
-                message.insert(
-                    "to_broadcaster_user_name".to_string(),
-                    Property::Text(msg.to_broadcaster_user_name.to_string()),
-                );
-                message.insert("viewers".to_string(), Property::I64(msg.viewers));
-                Ok(vec![Packet {
-                    port: "raid".to_string(),
-                    message,
-                }])
-            }
+    fn process_notification(&self, frame: &str) -> Result<Vec<Packet>> {
+        match Event::parse_websocket(frame).into_diagnostic()? {
+            EventsubWebsocketData::Notification { payload, .. } => Ok(event::packets(&payload)),
             _ => Ok(vec![]),
         }
     }
@@ -238,37 +134,35 @@ impl SubscriberProcess {
 #[async_trait]
 impl Process for SubscriberProcess {
     async fn run(&mut self, conn: &mut Connection) -> Result<()> {
+        // only the output ports with at least one connection are subscribed.
+        self.subscribe(&conn.outputs.names()).await?;
         self.passive_run(conn).await
     }
+
+    async fn shutdown(&mut self) -> Result<()> {
+        if let Some(subscriptions) = self.subscriptions.as_mut() {
+            subscriptions.close().await;
+        }
+        Ok(())
+    }
 }
 
 #[async_trait]
 impl PassiveProcess for SubscriberProcess {
     async fn handler(&mut self) -> Result<Vec<Packet>> {
-        use tokio_tungstenite::tungstenite::*;
-
-        match self.socket.next().await {
-            Some(Ok(Message::Text(msg))) => {
-                return match self.process_message(msg).await {
-                    Ok(ps) => Ok(ps),
-                    Err(err) => {
-                        // catch and we will continue to process for next message.
-                        eprintln!("message process error: {}", err);
-                        Ok(vec![])
-                    }
-                };
-            }
-            Some(err @ Err(Error::ConnectionClosed)) => {
-                // but if twitch says you should close connection, we want to along with that.
-                err.into_diagnostic()
-                    .wrap_err("Twitch connection was closed.")?;
-            }
-            None
-            | Some(Err(Error::Protocol(error::ProtocolError::ResetWithoutClosingHandshake))) => {
-                self.socket = Self::connect().await?;
-            }
-            _ => (),
-        }
-        Ok(vec![])
+        let subscriptions = self
+            .subscriptions
+            .as_mut()
+            .ok_or_else(|| miette!("subscriber is not running"))?;
+        // errors of the subscriptions, such as a revocation, stop the process.
+        let result = match subscriptions.next().await? {
+            SessionEvent::Notification { frame, .. } => self.process_notification(&frame),
+            _ => Ok(vec![]),
+        };
+        // catch and we will continue to process for next message.
+        Ok(result.unwrap_or_else(|err| {
+            tracing::warn!("message process error: {}", err);
+            vec![]
+        }))
     }
 }
@@ -0,0 +1,90 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+/// the response of `GET /oauth2/validate` for the user "damian".
+pub const VALIDATED: &str = r#"{"client_id":"cid","login":"damian","user_id":"42","scopes":["moderator:manage:shoutouts"],"expires_in":14400}"#;
+
+/// Request is what the stand-in received.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub body: String,
+}
+
+impl Request {
+    /// the method and the path, e.g. "GET /oauth2/validate".
+    pub fn line(&self) -> String {
+        format!("{} {}", self.method, self.path)
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_str(&self.body).unwrap()
+    }
+}
+
+pub type Requests = Arc<Mutex<Vec<Request>>>;
+
+/// serves the responses of `respond` over http, standing in for the twitch endpoints.
+/// returns the base url, and the requests received so far.
+pub async fn stand_in(
+    respond: impl Fn(&Request) -> (u16, String) + Send + Sync + 'static,
+) -> (url::Url, Requests) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let requests = Requests::default();
+    let received = requests.clone();
+    let respond = Arc::new(respond);
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            let received = received.clone();
+            let respond = respond.clone();
+            tokio::spawn(async move {
+                let mut socket = BufReader::new(socket);
+                let request = read(&mut socket).await;
+                let (status, body) = respond(&request);
+                received.lock().unwrap().push(request);
+                let response = format!(
+                    "HTTP/1.1 {status} X\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                socket
+                    .get_mut()
+                    .write_all(response.as_bytes())
+                    .await
+                    .unwrap();
+            });
+        }
+    });
+    (format!("http://{addr}/").parse().unwrap(), requests)
+}
+
+async fn read(socket: &mut BufReader<tokio::net::TcpStream>) -> Request {
+    let mut line = String::new();
+    socket.read_line(&mut line).await.unwrap();
+    let mut parts = line.split(' ');
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+    let mut length = 0;
+    loop {
+        let mut header = String::new();
+        socket.read_line(&mut header).await.unwrap();
+        if header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().unwrap();
+            }
+        }
+    }
+    let mut body = vec![0; length];
+    socket.read_exact(&mut body).await.unwrap();
+    Request {
+        method,
+        path,
+        body: String::from_utf8_lossy(&body).to_string(),
+    }
+}
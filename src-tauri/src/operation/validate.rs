@@ -0,0 +1,441 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::model::{IssueKind, Pipeline, Severity, ValidationIssue};
+use crate::operation::{outputs, pipeline::Context, Factory};
+
+struct Report(Vec<ValidationIssue>);
+
+impl Report {
+    fn push(
+        &mut self,
+        severity: Severity,
+        kind: IssueKind,
+        component: Option<&str>,
+        connection: Option<&str>,
+        message: String,
+    ) {
+        self.0.push(ValidationIssue {
+            severity,
+            kind,
+            component: component.map(str::to_string),
+            connection: connection.map(str::to_string),
+            message,
+        });
+    }
+}
+
+impl Factory {
+    /// checks the pipeline before it is deployed.
+    /// the ports are taken from the components the factory would create,
+    /// not from the ones saved in the editor.
+    pub fn validate(&self, pipeline: &Pipeline, ctx: &Context) -> Vec<ValidationIssue> {
+        use IssueKind::*;
+        use Severity::*;
+
+        let mut report = Report(vec![]);
+
+        // components are created with the settings, so nothing works without them.
+        for err in ctx.config.validate() {
+            report.push(Error, InvalidSettings, None, None, err.message);
+        }
+
+        let mut seen = HashSet::new();
+        for comp in &pipeline.components {
+            if !seen.insert(comp.id.as_str()) {
+                report.push(
+                    Error,
+                    DuplicateId,
+                    Some(&comp.id),
+                    None,
+                    format!("component id {} is used more than once", comp.id),
+                );
+            }
+        }
+        let mut seen = HashSet::new();
+        for conn in &pipeline.connections {
+            if !seen.insert(conn.id.as_str()) {
+                report.push(
+                    Error,
+                    DuplicateId,
+                    None,
+                    Some(&conn.id),
+                    format!("connection id {} is used more than once", conn.id),
+                );
+            }
+        }
+
+        let mut components = HashMap::new();
+        for mcomp in &pipeline.components {
+            if !self.0.contains_key(mcomp.kind.0.as_str()) {
+                report.push(
+                    Error,
+                    UnknownKind,
+                    Some(&mcomp.id),
+                    None,
+                    format!("{} is not a known component kind", mcomp.kind.0),
+                );
+                continue;
+            }
+            match self.create_component(&mcomp.kind, &mcomp.id, &mcomp.params, ctx) {
+                Ok(comp) => {
+                    components.insert(mcomp.id.as_str(), comp);
+                }
+                Err(err) => {
+                    report.push(Error, InvalidParams, Some(&mcomp.id), None, err.to_string())
+                }
+            }
+        }
+
+        for conn in &pipeline.connections {
+            let ids = [conn.source.parent.as_str(), conn.target.parent.as_str()];
+            let missing: Vec<&str> = ids
+                .into_iter()
+                .filter(|id| !pipeline.components.iter().any(|c| c.id == *id))
+                .collect();
+            if !missing.is_empty() {
+                report.push(
+                    Error,
+                    DanglingEdge,
+                    None,
+                    Some(&conn.id),
+                    format!("connection refers to missing {}", missing.join(", ")),
+                );
+                continue;
+            }
+
+            // components which failed to be created are reported above.
+            let (Some(source), Some(target)) = (components.get(ids[0]), components.get(ids[1]))
+            else {
+                continue;
+            };
+            if !outputs(source.as_ref())
+                .iter()
+                .any(|p| p.id.name == conn.source.name)
+            {
+                report.push(
+                    Error,
+                    UnknownPort,
+                    Some(ids[0]),
+                    Some(&conn.id),
+                    format!("{} has no output {}", ids[0], conn.source.name),
+                );
+            }
+            let inputs = target.inputs();
+            let Some(input) = inputs.iter().find(|p| p.id.name == conn.target.name) else {
+                report.push(
+                    Error,
+                    UnknownPort,
+                    Some(ids[1]),
+                    Some(&conn.id),
+                    format!("{} has no input {}", ids[1], conn.target.name),
+                );
+                continue;
+            };
+            for arg in input.required() {
+                if !conn.assignment.contains_key(arg) {
+                    report.push(
+                        Error,
+                        UnassignedArgument,
+                        Some(ids[1]),
+                        Some(&conn.id),
+                        format!("{} of {} is not assigned", arg, conn.target.name),
+                    );
+                }
+            }
+        }
+
+        for err in self.with_ports(pipeline, ctx).assignment_errors() {
+            report.push(
+                Error,
+                IncompatibleAssignment,
+                None,
+                Some(&err.connection),
+                err.message,
+            );
+        }
+
+        if let Some(cycle) = find_cycle(pipeline) {
+            report.push(
+                Error,
+                Cycle,
+                Some(&cycle[0]),
+                None,
+                format!("messages would loop through {}", cycle.join(" -> ")),
+            );
+        }
+
+        if pipeline.components.len() > 1 {
+            for comp in &pipeline.components {
+                let connected = pipeline
+                    .connections
+                    .iter()
+                    .any(|c| c.source.parent == comp.id || c.target.parent == comp.id);
+                if !connected {
+                    report.push(
+                        Warning,
+                        Unconnected,
+                        Some(&comp.id),
+                        None,
+                        format!("{} is not connected to any component", comp.id),
+                    );
+                }
+            }
+        }
+
+        report.0
+    }
+}
+
+/// returns the ids of components on a cycle, if the pipeline has one.
+fn find_cycle(pipeline: &Pipeline) -> Option<Vec<String>> {
+    let mut next: HashMap<&str, Vec<&str>> = HashMap::new();
+    for conn in &pipeline.connections {
+        next.entry(conn.source.parent.as_str())
+            .or_default()
+            .push(conn.target.parent.as_str());
+    }
+
+    fn visit<'a>(
+        id: &'a str,
+        next: &HashMap<&'a str, Vec<&'a str>>,
+        done: &mut HashSet<&'a str>,
+        path: &mut Vec<&'a str>,
+    ) -> Option<Vec<String>> {
+        if let Some(pos) = path.iter().position(|p| *p == id) {
+            let mut cycle: Vec<String> = path[pos..].iter().map(|p| p.to_string()).collect();
+            cycle.push(id.to_string());
+            return Some(cycle);
+        }
+        if done.contains(id) {
+            return None;
+        }
+        path.push(id);
+        for n in next.get(id).into_iter().flatten() {
+            if let Some(cycle) = visit(n, next, done, path) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        done.insert(id);
+        None
+    }
+
+    let mut done = HashSet::new();
+    for comp in &pipeline.components {
+        if let Some(cycle) = visit(&comp.id, &next, &mut done, &mut vec![]) {
+            return Some(cycle);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{ParamValue, Params};
+    use crate::operation::factory;
+    use crate::operation::template::TEMPLATE_PARAM;
+    use crate::operation::test::{context, fake, pipeline};
+
+    const EDGE: &str = "template-text-announce";
+
+    /// the pipeline whose edges go to the `input` port,
+    /// assigning each argument by the property of the same name.
+    fn to(mut pipeline: Pipeline, input: &str, args: &[&str]) -> Pipeline {
+        for conn in &mut pipeline.connections {
+            conn.target.name = input.to_string();
+            conn.assignment = args
+                .iter()
+                .map(|a| (a.to_string(), a.to_string()))
+                .collect();
+        }
+        pipeline
+    }
+
+    /// a template which announces its text, assigning the arguments.
+    fn announce(args: &[&str]) -> Pipeline {
+        let pipeline = pipeline(
+            &[("template", "Template"), ("announce", "TwitchAnnouncement")],
+            &[("template", "text", "announce")],
+        );
+        to(pipeline, "message", args)
+    }
+
+    /// the issues of the severity found in the pipeline, by kind, component and connection.
+    fn issues(
+        factory: &Factory,
+        pipeline: &Pipeline,
+        ctx: &Context,
+        severity: Severity,
+    ) -> Vec<(IssueKind, Option<String>, Option<String>)> {
+        factory
+            .validate(pipeline, ctx)
+            .into_iter()
+            .filter(|i| i.severity == severity)
+            .map(|i| (i.kind, i.component, i.connection))
+            .collect()
+    }
+
+    fn errors(pipeline: &Pipeline) -> Vec<(IssueKind, Option<String>, Option<String>)> {
+        issues(&factory(), pipeline, &context(), Severity::Error)
+    }
+
+    /// a factory of components which have an "in" input and "out" and "other" outputs.
+    fn fakes() -> Factory {
+        let (received, _) = tokio::sync::mpsc::unbounded_channel();
+        Factory::new(vec![fake("Fake", false, &received)])
+    }
+
+    fn some(id: &str) -> Option<String> {
+        Some(id.to_string())
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        let components = [("a", "Fake"), ("b", "Fake"), ("c", "Fake")];
+        assert_eq!(
+            find_cycle(&pipeline(
+                &components,
+                &[("a", "out", "b"), ("b", "out", "c"), ("a", "out", "c")]
+            )),
+            None
+        );
+        assert_eq!(
+            find_cycle(&pipeline(
+                &components,
+                &[("a", "out", "b"), ("b", "out", "c"), ("c", "out", "b")]
+            )),
+            Some(vec!["b".to_string(), "c".to_string(), "b".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_valid() {
+        assert_eq!(errors(&announce(&["text"])), vec![]);
+    }
+
+    #[test]
+    fn test_invalid_settings() {
+        let mut ctx = context();
+        ctx.config.channel = String::new();
+        assert_eq!(
+            issues(&factory(), &announce(&["text"]), &ctx, Severity::Error),
+            vec![(IssueKind::InvalidSettings, None, None)]
+        );
+    }
+
+    #[test]
+    fn test_unknown_kind() {
+        assert_eq!(
+            errors(&pipeline(&[("a", "Unknown")], &[])),
+            vec![(IssueKind::UnknownKind, some("a"), None)]
+        );
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let mut pipeline = announce(&["text"]);
+        pipeline.components[0].params =
+            Params::from([(TEMPLATE_PARAM.to_string(), ParamValue::Integer(1))]);
+        assert_eq!(
+            errors(&pipeline),
+            vec![(IssueKind::InvalidParams, some("template"), None)]
+        );
+    }
+
+    #[test]
+    fn test_dangling_edge() {
+        let pipeline = pipeline(&[("template", "Template")], &[("template", "text", "gone")]);
+        assert_eq!(
+            errors(&to(pipeline, "message", &["text"])),
+            vec![(IssueKind::DanglingEdge, None, some("template-text-gone"))]
+        );
+    }
+
+    #[test]
+    fn test_unknown_port() {
+        let mut pipeline = announce(&["text"]);
+        let mut from = pipeline.connections[0].clone();
+        from.id = "from".to_string();
+        from.source.name = "nope".to_string();
+        pipeline.connections[0].target.name = "nope".to_string();
+        pipeline.connections.push(from);
+        assert_eq!(
+            errors(&pipeline),
+            vec![
+                (IssueKind::UnknownPort, some("announce"), some(EDGE)),
+                (IssueKind::UnknownPort, some("template"), some("from")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unassigned_argument() {
+        assert_eq!(
+            errors(&announce(&[])),
+            vec![(IssueKind::UnassignedArgument, some("announce"), some(EDGE))]
+        );
+    }
+
+    #[test]
+    fn test_incompatible_assignment() {
+        let mut pipeline = pipeline(
+            &[
+                ("raids", "TwitchSubscriber"),
+                ("announce", "TwitchAnnouncement"),
+            ],
+            &[("raids", "raid", "announce")],
+        );
+        pipeline.connections[0].target.name = "message".to_string();
+        pipeline.connections[0].assignment = [("text".to_string(), "viewers".to_string())].into();
+        assert_eq!(
+            errors(&pipeline),
+            vec![(
+                IssueKind::IncompatibleAssignment,
+                None,
+                some("raids-raid-announce")
+            )]
+        );
+    }
+
+    #[test]
+    fn test_cycle() {
+        let pipeline = pipeline(
+            &[("a", "Fake"), ("b", "Fake")],
+            &[("a", "out", "b"), ("b", "out", "a")],
+        );
+        assert_eq!(
+            issues(&fakes(), &pipeline, &context(), Severity::Error),
+            vec![(IssueKind::Cycle, some("a"), None)]
+        );
+    }
+
+    #[test]
+    fn test_unconnected() {
+        let connected = pipeline(
+            &[("a", "Fake"), ("b", "Fake"), ("c", "Fake")],
+            &[("a", "out", "b")],
+        );
+        assert_eq!(
+            issues(&fakes(), &connected, &context(), Severity::Warning),
+            vec![(IssueKind::Unconnected, some("c"), None)]
+        );
+        // a single component is not expected to be connected.
+        let single = pipeline(&[("a", "Fake")], &[]);
+        assert!(issues(&fakes(), &single, &context(), Severity::Warning).is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_id() {
+        let mut pipeline = announce(&["text"]);
+        pipeline.components.push(pipeline.components[1].clone());
+        pipeline.connections.push(pipeline.connections[0].clone());
+        assert_eq!(
+            errors(&pipeline),
+            vec![
+                (IssueKind::DuplicateId, some("announce"), None),
+                (IssueKind::DuplicateId, None, some(EDGE)),
+            ]
+        );
+    }
+}
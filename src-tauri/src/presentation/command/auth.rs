@@ -0,0 +1,121 @@
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+use crate::model::{AUTH_FAILED, AUTH_UPDATED, SCOPES_CHECKED};
+use crate::operation::factory; // TODO: encapsulate by repository layer.
+use crate::presentation::command::editor::restore;
+use crate::presentation::protocol::{AuthStatus, DeviceLogin, MissingScopes};
+use crate::repository::Repositories;
+
+#[tauri::command]
+#[specta::specta]
+pub async fn auth_status(repos: State<'_, Mutex<Repositories>>) -> Result<AuthStatus, String> {
+    let auth = {
+        let repos = repos.lock().expect("Failed to lock auth repository");
+        repos.auth.clone()
+    };
+    Ok(auth.credentials().await.into())
+}
+
+/// validates the token against twitch, and lists the nodes which cannot run with its scopes.
+#[tauri::command]
+#[specta::specta]
+pub async fn missing_scopes(
+    repos: State<'_, Mutex<Repositories>>,
+) -> Result<Vec<MissingScopes>, String> {
+    let (auth, pipeline) = {
+        let repos = repos.lock().expect("Failed to lock auth repository");
+        (repos.context().auth, repos.pipeline.get())
+    };
+    let granted = auth.validate().await.map_err(|e| e.to_string())?;
+    Ok(factory()
+        .missing_scopes(&pipeline, &granted)
+        .into_iter()
+        .map(|m| m.into())
+        .collect())
+}
+
+/// checks scopes in background, and emits `scopes-checked` with nodes which cannot run.
+pub fn check_scopes(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let repos = app.state::<Mutex<Repositories>>();
+        match missing_scopes(repos).await {
+            Ok(missing) => {
+                for m in &missing {
+                    tracing::warn!(
+                        component = %m.component,
+                        kind = %m.kind,
+                        "cannot run, missing scopes: {}",
+                        m.scopes.join(", ")
+                    );
+                }
+                app.emit_all(SCOPES_CHECKED, missing).unwrap();
+            }
+            Err(err) => tracing::error!("failed to check token scopes: {}", err),
+        }
+    });
+}
+
+/// starts the device code login. the returned code has to be entered on the verification page,
+/// and `auth-updated` (or `auth-failed`) is emitted once the user has answered.
+#[tauri::command]
+#[specta::specta]
+pub async fn login(
+    app: AppHandle,
+    repos: State<'_, Mutex<Repositories>>,
+) -> Result<DeviceLogin, String> {
+    let (client_id, auth) = {
+        let repos = repos.lock().expect("Failed to lock auth repository");
+        (repos.config.get().client_id, repos.auth.clone())
+    };
+    if client_id.is_empty() {
+        return Err("client_id must be set before login.".to_string());
+    }
+
+    let scopes = factory().scopes();
+    let code = auth
+        .oauth()
+        .request_device_code(&client_id, &scopes)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let pending = code.clone();
+    tauri::async_runtime::spawn(async move {
+        let res = match auth
+            .oauth()
+            .wait_device_approval(&client_id, &scopes, &pending)
+            .await
+        {
+            Ok(credentials) => auth.login(credentials).await,
+            Err(err) => Err(err),
+        };
+
+        match res {
+            Ok(()) => {
+                // components were created without a token, so deploy them again.
+                let repos = app.state::<Mutex<Repositories>>();
+                restore(
+                    &app,
+                    &mut repos.lock().expect("Failed to lock pipeline repository"),
+                );
+                app.emit_all(AUTH_UPDATED, "login").unwrap();
+                check_scopes(app);
+            }
+            Err(err) => app.emit_all(AUTH_FAILED, err.to_string()).unwrap(),
+        }
+    });
+
+    Ok(code.into())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn logout(app: AppHandle, repos: State<'_, Mutex<Repositories>>) -> Result<(), String> {
+    let auth = {
+        let repos = repos.lock().expect("Failed to lock auth repository");
+        repos.auth.clone()
+    };
+    auth.logout().await.map_err(|e| e.to_string())?;
+    app.emit_all(AUTH_UPDATED, "logout").unwrap();
+    Ok(())
+}
@@ -2,8 +2,12 @@ use std::sync::Mutex;
 use tauri::{AppHandle, Manager, State};
 
 use crate::model::{Kind, PIPELINE_UPDATED};
-use crate::operation::factory; // TODO: encapsulate by repository layer.
-use crate::presentation::protocol::{Candidate, Node, NodeData, Position};
+use crate::operation::pipeline::{Packet, Property as OProperty};
+use crate::operation::{factory, outputs}; // TODO: encapsulate by repository layer.
+use crate::presentation::protocol::{
+    params_from, params_into, Candidate, InjectPreset, Message, Node, NodeData, Params, Position,
+    RestartPolicy,
+};
 use crate::repository::Repositories;
 
 #[tauri::command]
@@ -14,6 +18,7 @@ pub fn candidates() -> Vec<Candidate> {
         res.push(Candidate {
             kind: c.kind.0.clone(),
             label: c.label.to_string(),
+            params: c.params.into_iter().map(|p| p.into()).collect(),
         });
     }
     res
@@ -27,26 +32,147 @@ pub fn create_component(
     repos: State<'_, Mutex<Repositories>>,
     kind: String,
     position: Position,
-) {
+) -> Result<(), String> {
     let mut repos = repos.lock().expect("Failed to lock pipeline repository");
 
     let id = ulid::Ulid::new().to_string();
 
-    let Ok(comp) = factory().create_component(&Kind(kind.clone()), id.as_str()) else {
-        return;
-    };
+    let ctx = repos.context();
+    let kind = Kind(kind);
+    let params = factory()
+        .resolve_params(&kind, &Default::default())
+        .map_err(|e| e.to_string())?;
+    let comp = factory()
+        .create_component(&kind, id.as_str(), &params, &ctx)
+        .map_err(|e| e.to_string())?;
 
     let node = Node {
         id,
-        kind,
+        kind: kind.0,
         position,
         data: NodeData {
             label: comp.label().to_string(),
             inputs: comp.inputs().into_iter().map(|i| i.into()).collect(),
-            outputs: comp.outputs().into_iter().map(|o| o.into()).collect(),
+            outputs: outputs(comp.as_ref())
+                .into_iter()
+                .map(|o| o.into())
+                .collect(),
+            params: params_from(params),
+            ..Default::default()
         },
     };
 
-    repos.editor.insert_node(node);
+    repos.editor.insert_node(node).map_err(|e| e.to_string())?;
     app.emit_all(PIPELINE_UPDATED, "create_component").unwrap();
+    Ok(())
+}
+
+/// replaces the per-node parameters of the node after validating them by the schema,
+/// and regenerates its ports since they can depend on the parameters.
+#[tauri::command]
+#[specta::specta]
+pub fn update_params(
+    app: AppHandle,
+    repos: State<'_, Mutex<Repositories>>,
+    id: String,
+    params: Params,
+) -> Result<(), String> {
+    let mut repos = repos.lock().expect("Failed to lock pipeline repository");
+
+    let Some(mut node) = repos.editor.get().nodes.into_iter().find(|n| n.id == id) else {
+        return Err(format!("node {} not found", id));
+    };
+
+    let ctx = repos.context();
+    let kind = Kind(node.kind.clone());
+    let params = factory()
+        .resolve_params(&kind, &params_into(params))
+        .map_err(|e| e.to_string())?;
+    let comp = factory()
+        .create_component(&kind, id.as_str(), &params, &ctx)
+        .map_err(|e| e.to_string())?;
+
+    node.data.inputs = comp.inputs().into_iter().map(|i| i.into()).collect();
+    node.data.outputs = outputs(comp.as_ref())
+        .into_iter()
+        .map(|o| o.into())
+        .collect();
+    node.data.params = params_from(params);
+
+    repos.editor.update_node(node).map_err(|e| e.to_string())?;
+    app.emit_all(PIPELINE_UPDATED, "update_params").unwrap();
+    Ok(())
+}
+
+/// sets whether the process of the node is restarted after it stops.
+#[tauri::command]
+#[specta::specta]
+pub fn set_restart_policy(
+    app: AppHandle,
+    repos: State<'_, Mutex<Repositories>>,
+    id: String,
+    restart: RestartPolicy,
+) -> Result<(), String> {
+    let mut repos = repos.lock().expect("Failed to lock pipeline repository");
+
+    let Some(mut node) = repos.editor.get().nodes.into_iter().find(|n| n.id == id) else {
+        return Err(format!("node {} not found", id));
+    };
+    node.data.restart = restart;
+
+    repos.editor.update_node(node).map_err(|e| e.to_string())?;
+    app.emit_all(PIPELINE_UPDATED, "set_restart_policy")
+        .unwrap();
+    Ok(())
+}
+
+/// messages to start from when injecting, one for each output port of the deployed node.
+#[tauri::command]
+#[specta::specta]
+pub fn inject_presets(repos: State<'_, Mutex<Repositories>>, id: String) -> Vec<InjectPreset> {
+    let repos = repos.lock().expect("Failed to lock pipeline repository");
+    let pipeline = repos.pipeline.get();
+    let Some(comp) = pipeline.components.iter().find(|c| c.id == id) else {
+        return vec![];
+    };
+    comp.outputs
+        .iter()
+        .map(|port| InjectPreset {
+            port: port.id.name.clone(),
+            message: port
+                .property_names
+                .iter()
+                .map(|name| {
+                    let zero = OProperty::zero(port.property_type(name));
+                    (name.clone(), zero.into())
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// sends the message from the output port of the running node,
+/// and it is delivered through the connections like the ones the node emits.
+#[tauri::command]
+#[specta::specta]
+pub async fn inject(
+    repos: State<'_, Mutex<Repositories>>,
+    id: String,
+    port: String,
+    message: Message,
+) -> Result<(), String> {
+    let wiring = {
+        let repos = repos.lock().expect("Failed to lock pipeline repository");
+        repos.pipeline.wiring(&id)
+    };
+    let Some(wiring) = wiring else {
+        return Err(format!("node {} is not running", id));
+    };
+
+    tracing::info!(component = %id, port = %port, "injecting a message");
+    let packet = Packet {
+        port,
+        message: message.into_iter().map(|(k, v)| (k, v.into())).collect(),
+    };
+    wiring.inject(packet).await.map_err(|e| e.to_string())
 }
@@ -1,8 +1,9 @@
 use std::sync::Mutex;
 use tauri::{AppHandle, Manager, State};
 
-use crate::model::PIPELINE_UPDATED;
-use crate::presentation::protocol::Assignment;
+use crate::model::{Pipeline, PIPELINE_UPDATED};
+use crate::operation::factory; // TODO: encapsulate by repository layer.
+use crate::presentation::protocol::{Assignment, AssignmentError, MissingPolicy, Traffic};
 use crate::repository::Repositories;
 
 #[tauri::command]
@@ -14,14 +15,16 @@ pub fn add_edge(
     target: String,
     source_handle: String,
     target_handle: String,
-) {
+) -> Result<(), String> {
     let mut repos = repos.lock().expect("Failed to lock pipeline repository");
 
     repos
         .editor
-        .add_edge(source, target, source_handle, target_handle);
+        .add_edge(source, target, source_handle, target_handle)
+        .map_err(|e| e.to_string())?;
 
     app.emit_all(PIPELINE_UPDATED, "add_edge").unwrap();
+    Ok(())
 }
 
 #[tauri::command]
@@ -33,14 +36,19 @@ pub fn remove_edge(
     target: String,
     source_handle: String,
     target_handle: String,
-) {
+) -> Result<(), String> {
     let mut repos = repos.lock().expect("Failed to lock pipeline repository");
 
     repos
         .editor
-        .remove_edge(source, target, source_handle, target_handle);
+        .remove_edge(source, target, source_handle, target_handle)
+        .map_err(|e| e.to_string())?;
+    let editor = repos.editor.get();
+    let edges: Vec<&str> = editor.edges.iter().map(|e| e.id.as_str()).collect();
+    repos.monitor.tap().retain(&edges);
 
     app.emit_all(PIPELINE_UPDATED, "remove_edge").unwrap();
+    Ok(())
 }
 
 #[tauri::command]
@@ -50,10 +58,70 @@ pub fn set_assignment(
     repos: State<'_, Mutex<Repositories>>,
     id: String,
     assignment: Assignment,
-) {
+) -> Result<(), Vec<AssignmentError>> {
     let mut repos = repos.lock().expect("Failed to lock pipeline repository");
 
-    repos.editor.set_assignment(id, assignment);
+    // check the assignment on a copy so that incompatible ones are never stored.
+    let mut editor = repos.editor.get();
+    editor.set_assignment(id.clone(), assignment.clone());
+    let pipeline: Pipeline = editor.into();
+    let errors: Vec<AssignmentError> = factory()
+        .with_ports(&pipeline, &repos.context())
+        .assignment_errors()
+        .into_iter()
+        .filter(|e| e.connection == id)
+        .map(|e| e.into())
+        .collect();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    repos
+        .editor
+        .set_assignment(id, assignment)
+        .map_err(AssignmentError::other)?;
 
     app.emit_all(PIPELINE_UPDATED, "set_assignment").unwrap();
+    Ok(())
+}
+
+/// sets what the edge does with messages which lack an assigned property.
+#[tauri::command]
+#[specta::specta]
+pub fn set_missing_policy(
+    app: AppHandle,
+    repos: State<'_, Mutex<Repositories>>,
+    id: String,
+    missing: MissingPolicy,
+) -> Result<(), String> {
+    let mut repos = repos.lock().expect("Failed to lock pipeline repository");
+
+    repos
+        .editor
+        .set_missing_policy(id, missing)
+        .map_err(|e| e.to_string())?;
+
+    app.emit_all(PIPELINE_UPDATED, "set_missing_policy")
+        .unwrap();
+    Ok(())
+}
+
+/// starts or stops recording the packets delivered through the edge.
+/// the recorded packets are kept only while the app runs.
+#[tauri::command]
+#[specta::specta]
+pub fn set_tap(repos: State<'_, Mutex<Repositories>>, id: String, enabled: bool) {
+    let repos = repos.lock().expect("Failed to lock pipeline repository");
+    repos.monitor.tap().enable(&id, enabled);
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn traffic(repos: State<'_, Mutex<Repositories>>, id: String) -> Traffic {
+    let repos = repos.lock().expect("Failed to lock pipeline repository");
+    let tap = repos.monitor.tap();
+    Traffic {
+        enabled: tap.is_enabled(&id),
+        packets: tap.packets(&id).into_iter().map(|p| p.into()).collect(),
+    }
 }
@@ -1,8 +1,10 @@
 use std::sync::Mutex;
 use tauri::{AppHandle, Manager, State};
 
-use crate::model::PIPELINE_UPDATED;
-use crate::presentation::protocol::Editor;
+use crate::model::{Pipeline, Severity, PIPELINE_REJECTED, PIPELINE_UPDATED};
+use crate::operation::factory; // TODO: encapsulate by repository layer.
+use crate::presentation::command::auth::check_scopes;
+use crate::presentation::protocol::{Editor, ValidationIssue};
 use crate::repository::Repositories;
 
 #[tauri::command]
@@ -12,11 +14,46 @@ pub fn editor(repos: State<'_, Mutex<Repositories>>) -> Editor {
     repos.editor.get()
 }
 
+/// saves the editor, and replaces the running pipeline if it passes the validation.
+/// returns the warnings on success, and all issues if the pipeline is not deployed.
 #[tauri::command]
 #[specta::specta]
-pub fn update_editor(app: AppHandle, repos: State<'_, Mutex<Repositories>>, updated: Editor) {
+pub fn update_editor(
+    app: AppHandle,
+    repos: State<'_, Mutex<Repositories>>,
+    updated: Editor,
+) -> Result<Vec<ValidationIssue>, Vec<ValidationIssue>> {
     let mut repos = repos.lock().expect("Failed to lock pipeline repository");
-    repos.editor.set(updated.clone());
-    repos.pipeline.set(updated.into());
+
+    repos
+        .editor
+        .set(updated.clone())
+        .map_err(ValidationIssue::other)?;
+    // forget the packets recorded on the removed edges.
+    let edges: Vec<&str> = updated.edges.iter().map(|e| e.id.as_str()).collect();
+    repos.monitor.tap().retain(&edges);
+
+    let pipeline: Pipeline = updated.into();
+    let ctx = repos.context();
+    let issues = factory().validate(&pipeline, &ctx);
+    let blocked = issues.iter().any(|i| i.severity == Severity::Error);
+    let issues: Vec<ValidationIssue> = issues.into_iter().map(|i| i.into()).collect();
+    if blocked {
+        // keep the running pipeline as is.
+        return Err(issues);
+    }
+
+    repos.pipeline.set(pipeline, &ctx);
     app.emit_all(PIPELINE_UPDATED, "update_editor").unwrap();
+    drop(repos);
+    check_scopes(app);
+    Ok(issues)
+}
+
+/// deploys the persisted editor again, and tells the editor why if it is not deployed.
+pub fn restore(app: &AppHandle, repos: &mut Repositories) {
+    let issues: Vec<ValidationIssue> = repos.restore().into_iter().map(|i| i.into()).collect();
+    if !issues.is_empty() {
+        app.emit_all(PIPELINE_REJECTED, issues).unwrap();
+    }
 }
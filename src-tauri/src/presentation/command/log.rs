@@ -0,0 +1,18 @@
+use std::sync::Mutex;
+use tauri::State;
+
+use crate::presentation::protocol::LogEntry;
+use crate::repository::Repositories;
+
+/// recent log entries, only of the node if it is given.
+#[tauri::command]
+#[specta::specta]
+pub fn logs(repos: State<'_, Mutex<Repositories>>, node: Option<String>) -> Vec<LogEntry> {
+    let repos = repos.lock().expect("Failed to lock pipeline repository");
+    repos
+        .logs
+        .entries(node.as_deref())
+        .into_iter()
+        .map(|e| e.into())
+        .collect()
+}
@@ -1,3 +1,7 @@
+pub mod auth;
 pub mod component;
 pub mod edge;
 pub mod editor;
+pub mod log;
+pub mod pipeline;
+pub mod settings;
@@ -0,0 +1,32 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::State;
+
+use crate::presentation::protocol::Health;
+use crate::repository::Repositories;
+
+/// the status of the running processes. nodes which are not deployed are not included.
+#[tauri::command]
+#[specta::specta]
+pub fn health(repos: State<'_, Mutex<Repositories>>) -> Vec<Health> {
+    let repos = repos.lock().expect("Failed to lock pipeline repository");
+    repos
+        .pipeline
+        .health()
+        .into_iter()
+        .map(|h| h.into())
+        .collect()
+}
+
+/// the number of redelivered EventSub notifications dropped for each node.
+#[tauri::command]
+#[specta::specta]
+pub fn duplicates(repos: State<'_, Mutex<Repositories>>) -> HashMap<String, u32> {
+    let repos = repos.lock().expect("Failed to lock pipeline repository");
+    repos
+        .eventsub
+        .duplicates()
+        .into_iter()
+        .map(|(component, n)| (component, n.try_into().unwrap_or(u32::MAX)))
+        .collect()
+}
@@ -0,0 +1,47 @@
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+use crate::model::SETTINGS_UPDATED;
+use crate::presentation::command::auth::check_scopes;
+use crate::presentation::command::editor::restore;
+use crate::presentation::protocol::{Settings, SettingsError};
+use crate::repository::Repositories;
+
+#[tauri::command]
+#[specta::specta]
+pub fn settings(repos: State<'_, Mutex<Repositories>>) -> Settings {
+    let repos = repos.lock().expect("Failed to lock settings repository");
+    repos.config.stored().into()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn update_settings(
+    app: AppHandle,
+    repos: State<'_, Mutex<Repositories>>,
+    updated: Settings,
+) -> Result<(), Vec<SettingsError>> {
+    let mut repos = repos.lock().expect("Failed to lock settings repository");
+
+    let config: crate::config::Config = updated.into();
+    let errors = config.clone().with_envs().validate();
+    if !errors.is_empty() {
+        return Err(errors.into_iter().map(|e| e.into()).collect());
+    }
+
+    repos.config.set(config).map_err(|e| {
+        vec![SettingsError {
+            field: String::new(),
+            message: e.to_string(),
+        }]
+    })?;
+
+    // components hold their own copy of settings, so the pipeline has to be rebuilt.
+    restore(&app, &mut repos);
+
+    app.emit_all(SETTINGS_UPDATED, "update_settings").unwrap();
+    // the token may be replaced.
+    drop(repos);
+    check_scopes(app);
+    Ok(())
+}
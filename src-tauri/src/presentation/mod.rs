@@ -1,3 +1,4 @@
 pub mod command;
+pub mod monitor;
 pub mod protocol;
 pub mod tray;
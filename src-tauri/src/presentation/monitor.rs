@@ -0,0 +1,47 @@
+use tauri::{AppHandle, Manager};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::model::{HEALTH_UPDATED, LOG_APPENDED, PACKET_DROPPED};
+use crate::operation::pipeline::{Monitor, MonitorEvent};
+use crate::presentation::protocol::{DroppedPacket, Health, LogEntry};
+use crate::repository::log::Logs;
+
+/// forwards events reported by the running pipeline to the editor.
+pub fn forward(app: AppHandle, monitor: &Monitor) {
+    let mut events = monitor.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(MonitorEvent::Dropped(dropped)) => {
+                    let dropped: DroppedPacket = dropped.into();
+                    app.emit_all(PACKET_DROPPED, dropped).unwrap();
+                }
+                Ok(MonitorEvent::Health(health)) => {
+                    let health: Health = health.into();
+                    app.emit_all(HEALTH_UPDATED, health).unwrap();
+                }
+                // the editor only shows the latest state, so skipped events are not a problem.
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            }
+        }
+    });
+}
+
+/// forwards new log entries to the log panel of the editor.
+pub fn forward_logs(app: AppHandle, logs: &Logs) {
+    let mut entries = logs.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match entries.recv().await {
+                Ok(entry) => {
+                    let entry: LogEntry = entry.into();
+                    app.emit_all(LOG_APPENDED, entry).unwrap();
+                }
+                // the panel can fetch the skipped ones by the command.
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            }
+        }
+    });
+}
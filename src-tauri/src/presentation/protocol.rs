@@ -6,6 +6,179 @@ use ulid::Ulid;
 pub struct Candidate {
     pub kind: String,
     pub label: String,
+    pub params: Vec<ParamSpec>,
+}
+
+// specta does not allow i64, and integer parameters are small enough for i32.
+#[derive(Type, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ParamValue {
+    Integer(i32),
+    Text(String),
+}
+
+impl From<crate::model::ParamValue> for ParamValue {
+    fn from(v: crate::model::ParamValue) -> ParamValue {
+        use crate::model::ParamValue::*;
+        match v {
+            Text(v) => ParamValue::Text(v),
+            Integer(v) => ParamValue::Integer(v.clamp(i32::MIN.into(), i32::MAX.into()) as i32),
+        }
+    }
+}
+
+#[allow(clippy::from_over_into)]
+impl Into<crate::model::ParamValue> for ParamValue {
+    fn into(self) -> crate::model::ParamValue {
+        use crate::model::ParamValue::*;
+        match self {
+            ParamValue::Text(v) => Text(v),
+            ParamValue::Integer(v) => Integer(v.into()),
+        }
+    }
+}
+
+pub type Params = std::collections::HashMap<String, ParamValue>;
+
+pub fn params_from(v: crate::model::Params) -> Params {
+    v.into_iter().map(|(k, v)| (k, v.into())).collect()
+}
+
+pub fn params_into(v: Params) -> crate::model::Params {
+    v.into_iter().map(|(k, v)| (k, v.into())).collect()
+}
+
+/// ParamSpec tells the editor how to render the input of a per-node parameter.
+/// `type` is one of "text", "multiline", "integer" and "choice".
+#[derive(Type, Debug, Clone, Serialize, Deserialize)]
+pub struct ParamSpec {
+    pub name: String,
+    pub label: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub options: Vec<String>,
+    pub default: ParamValue,
+}
+
+impl From<crate::model::ParamSpec> for ParamSpec {
+    fn from(v: crate::model::ParamSpec) -> ParamSpec {
+        use crate::model::ParamType::*;
+        let (kind, options) = match v.ty {
+            Text => ("text", vec![]),
+            Multiline => ("multiline", vec![]),
+            Integer => ("integer", vec![]),
+            Choice(options) => ("choice", options),
+        };
+        ParamSpec {
+            name: v.name,
+            label: v.label,
+            kind: kind.to_string(),
+            options,
+            default: v.default.into(),
+        }
+    }
+}
+
+#[derive(Type, Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Settings {
+    pub channel: String,
+    #[serde(rename = "clientId")]
+    pub client_id: String,
+    pub token: String,
+}
+
+impl From<crate::config::Config> for Settings {
+    fn from(v: crate::config::Config) -> Settings {
+        Settings {
+            channel: v.channel,
+            client_id: v.client_id,
+            token: v.token,
+        }
+    }
+}
+
+#[allow(clippy::from_over_into)]
+impl Into<crate::config::Config> for Settings {
+    fn into(self) -> crate::config::Config {
+        crate::config::Config {
+            channel: self.channel,
+            client_id: self.client_id,
+            token: self.token,
+        }
+    }
+}
+
+#[derive(Type, Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SettingsError {
+    pub field: String,
+    pub message: String,
+}
+
+impl From<crate::config::ConfigError> for SettingsError {
+    fn from(v: crate::config::ConfigError) -> SettingsError {
+        SettingsError {
+            field: v.field.to_string(),
+            message: v.message,
+        }
+    }
+}
+
+#[derive(Type, Debug, Default, Clone, Serialize, Deserialize)]
+pub struct AuthStatus {
+    #[serde(rename = "loggedIn")]
+    pub logged_in: bool,
+    pub login: String,
+    pub scopes: Vec<String>,
+}
+
+impl From<Option<crate::operation::twitch::Credentials>> for AuthStatus {
+    fn from(v: Option<crate::operation::twitch::Credentials>) -> AuthStatus {
+        match v {
+            Some(c) => AuthStatus {
+                logged_in: true,
+                login: c.login,
+                scopes: c.scopes,
+            },
+            None => AuthStatus::default(),
+        }
+    }
+}
+
+#[derive(Type, Debug, Default, Clone, Serialize, Deserialize)]
+pub struct MissingScopes {
+    pub component: String,
+    pub kind: String,
+    pub scopes: Vec<String>,
+}
+
+impl From<crate::model::MissingScopes> for MissingScopes {
+    fn from(v: crate::model::MissingScopes) -> MissingScopes {
+        MissingScopes {
+            component: v.component,
+            kind: v.kind.0,
+            scopes: v.scopes,
+        }
+    }
+}
+
+#[derive(Type, Debug, Default, Clone, Serialize, Deserialize)]
+pub struct DeviceLogin {
+    #[serde(rename = "userCode")]
+    pub user_code: String,
+    #[serde(rename = "verificationUri")]
+    pub verification_uri: String,
+    #[serde(rename = "expiresIn")]
+    pub expires_in: u32,
+}
+
+impl From<crate::operation::twitch::DeviceCode> for DeviceLogin {
+    fn from(v: crate::operation::twitch::DeviceCode) -> DeviceLogin {
+        DeviceLogin {
+            user_code: v.user_code,
+            verification_uri: v.verification_uri,
+            expires_in: v.expires_in.try_into().unwrap_or(u32::MAX),
+        }
+    }
 }
 
 #[derive(Type, Debug, Default, Clone, Serialize, Deserialize)]
@@ -51,6 +224,20 @@ impl Editor {
             .map(|i| self.edges.remove(i));
     }
 
+    pub fn update_node(&mut self, node: Node) {
+        let Some(n) = self.nodes.iter_mut().find(|n| n.id == node.id) else {
+            return;
+        };
+        *n = node;
+    }
+
+    pub fn set_missing_policy(&mut self, id: String, missing: MissingPolicy) {
+        let Some(edge) = self.edges.iter_mut().find(|e| e.id == id) else {
+            return;
+        };
+        edge.data.missing = missing;
+    }
+
     pub fn set_assignment(&mut self, id: String, assignment: Assignment) {
         let Some(mut edge) = self.edges.iter_mut().find(|e| e.id == id) else {
             return;
@@ -86,6 +273,8 @@ impl Into<crate::model::Component> for Node {
             kind: crate::model::Kind(self.kind),
             inputs: self.data.inputs.into_iter().map(|n| n.into()).collect(),
             outputs: self.data.outputs.into_iter().map(|n| n.into()).collect(),
+            params: params_into(self.data.params),
+            restart: self.data.restart.into(),
         }
     }
 }
@@ -101,6 +290,31 @@ pub struct NodeData {
     pub label: String,
     pub inputs: Vec<InputPort>,
     pub outputs: Vec<OutputPort>,
+    #[serde(default)]
+    pub params: Params,
+    #[serde(default)]
+    pub restart: RestartPolicy,
+}
+
+#[derive(Type, Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RestartPolicy {
+    Never,
+    #[default]
+    OnFailure,
+    Always,
+}
+
+#[allow(clippy::from_over_into)]
+impl Into<crate::model::RestartPolicy> for RestartPolicy {
+    fn into(self) -> crate::model::RestartPolicy {
+        use crate::model::RestartPolicy::*;
+        match self {
+            RestartPolicy::Never => Never,
+            RestartPolicy::OnFailure => OnFailure,
+            RestartPolicy::Always => Always,
+        }
+    }
 }
 
 #[derive(Type, Debug, Default, Clone, Serialize, Deserialize)]
@@ -119,6 +333,259 @@ pub struct Edge {
 #[derive(Type, Debug, Default, Clone, Serialize, Deserialize)]
 pub struct EdgeData {
     pub assignment: Assignment,
+    #[serde(default)]
+    pub missing: MissingPolicy,
+}
+
+#[derive(Type, Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MissingPolicy {
+    #[default]
+    Skip,
+    FillDefault,
+    Error,
+}
+
+impl From<crate::model::MissingPolicy> for MissingPolicy {
+    fn from(v: crate::model::MissingPolicy) -> MissingPolicy {
+        use crate::model::MissingPolicy::*;
+        match v {
+            Skip => MissingPolicy::Skip,
+            FillDefault => MissingPolicy::FillDefault,
+            Error => MissingPolicy::Error,
+        }
+    }
+}
+
+#[allow(clippy::from_over_into)]
+impl Into<crate::model::MissingPolicy> for MissingPolicy {
+    fn into(self) -> crate::model::MissingPolicy {
+        use crate::model::MissingPolicy::*;
+        match self {
+            MissingPolicy::Skip => Skip,
+            MissingPolicy::FillDefault => FillDefault,
+            MissingPolicy::Error => Error,
+        }
+    }
+}
+
+/// Property mirrors the property of a message, in the same format as it is serialized.
+#[derive(Type, Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum Property {
+    Text(String),
+    // specta does not allow i64, and it is a number in TypeScript anyway.
+    I64(#[specta(type = f64)] i64),
+    F64(f64),
+    Bool(bool),
+    Timestamp(String),
+    List(Vec<Property>),
+    Map(std::collections::HashMap<String, Property>),
+}
+
+impl From<crate::operation::pipeline::Property> for Property {
+    fn from(v: crate::operation::pipeline::Property) -> Property {
+        use crate::operation::pipeline::Property::*;
+        match v {
+            Text(v) => Property::Text(v),
+            I64(v) => Property::I64(v),
+            F64(v) => Property::F64(v),
+            Bool(v) => Property::Bool(v),
+            Timestamp(v) => Property::Timestamp(v),
+            List(vs) => Property::List(vs.into_iter().map(|v| v.into()).collect()),
+            Map(vs) => Property::Map(vs.into_iter().map(|(k, v)| (k, v.into())).collect()),
+        }
+    }
+}
+
+#[allow(clippy::from_over_into)]
+impl Into<crate::operation::pipeline::Property> for Property {
+    fn into(self) -> crate::operation::pipeline::Property {
+        use crate::operation::pipeline::Property::*;
+        match self {
+            Property::Text(v) => Text(v),
+            Property::I64(v) => I64(v),
+            Property::F64(v) => F64(v),
+            Property::Bool(v) => Bool(v),
+            Property::Timestamp(v) => Timestamp(v),
+            Property::List(vs) => List(vs.into_iter().map(|v| v.into()).collect()),
+            Property::Map(vs) => Map(vs.into_iter().map(|(k, v)| (k, v.into())).collect()),
+        }
+    }
+}
+
+pub type Message = std::collections::HashMap<String, Property>;
+
+/// InjectPreset is a message with every declared property of an output port,
+/// filled with the zero values, to be edited before injecting it.
+#[derive(Type, Debug, Default, Clone, Serialize, Deserialize)]
+pub struct InjectPreset {
+    pub port: String,
+    pub message: Message,
+}
+
+/// Traffic is the recent packets of a tapped edge.
+#[derive(Type, Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Traffic {
+    pub enabled: bool,
+    pub packets: Vec<TappedPacket>,
+}
+
+#[derive(Type, Debug, Default, Clone, Serialize, Deserialize)]
+pub struct TappedPacket {
+    pub edge: String,
+    /// milliseconds since the unix epoch.
+    pub timestamp: f64,
+    pub port: String,
+    /// the properties of the message, formatted for display.
+    pub message: std::collections::HashMap<String, String>,
+}
+
+impl From<crate::operation::pipeline::TappedPacket> for TappedPacket {
+    fn from(v: crate::operation::pipeline::TappedPacket) -> TappedPacket {
+        TappedPacket {
+            edge: v.connection,
+            timestamp: v.timestamp as f64,
+            port: v.port,
+            message: v
+                .message
+                .into_iter()
+                .map(|(k, v)| (k, v.to_string()))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Type, Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    #[default]
+    Info,
+    Warn,
+    Error,
+}
+
+impl From<crate::model::LogLevel> for LogLevel {
+    fn from(v: crate::model::LogLevel) -> LogLevel {
+        use crate::model::LogLevel::*;
+        match v {
+            Trace => LogLevel::Trace,
+            Debug => LogLevel::Debug,
+            Info => LogLevel::Info,
+            Warn => LogLevel::Warn,
+            Error => LogLevel::Error,
+        }
+    }
+}
+
+#[derive(Type, Debug, Default, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    /// milliseconds since the unix epoch.
+    pub timestamp: f64,
+    pub level: LogLevel,
+    pub target: String,
+    pub node: Option<String>,
+    pub kind: Option<String>,
+    pub message: String,
+    pub fields: std::collections::HashMap<String, String>,
+}
+
+impl From<crate::model::LogEntry> for LogEntry {
+    fn from(v: crate::model::LogEntry) -> LogEntry {
+        LogEntry {
+            timestamp: v.timestamp as f64,
+            level: v.level.into(),
+            target: v.target,
+            node: v.component,
+            kind: v.kind,
+            message: v.message,
+            fields: v.fields.into_iter().collect(),
+        }
+    }
+}
+
+#[derive(Type, Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProcessState {
+    #[default]
+    Initializing,
+    Running,
+    Restarting,
+    Failed,
+    Stopped,
+}
+
+impl From<crate::model::ProcessState> for ProcessState {
+    fn from(v: crate::model::ProcessState) -> ProcessState {
+        use crate::model::ProcessState::*;
+        match v {
+            Initializing => ProcessState::Initializing,
+            Running => ProcessState::Running,
+            Restarting => ProcessState::Restarting,
+            Failed => ProcessState::Failed,
+            Stopped => ProcessState::Stopped,
+        }
+    }
+}
+
+#[derive(Type, Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Health {
+    pub node: String,
+    pub state: ProcessState,
+    pub error: Option<String>,
+    pub restarts: u32,
+}
+
+impl From<crate::model::Health> for Health {
+    fn from(v: crate::model::Health) -> Health {
+        Health {
+            node: v.component,
+            state: v.state.into(),
+            error: v.error,
+            restarts: v.restarts,
+        }
+    }
+}
+
+#[derive(Type, Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DroppedPacket {
+    Missing {
+        edge: String,
+        argument: Argument,
+        property: PropertyName,
+        policy: MissingPolicy,
+    },
+    Discarded {
+        node: String,
+        reason: String,
+    },
+}
+
+impl From<crate::model::DroppedPacket> for DroppedPacket {
+    fn from(v: crate::model::DroppedPacket) -> DroppedPacket {
+        match v {
+            crate::model::DroppedPacket::Missing {
+                connection,
+                argument,
+                property,
+                policy,
+            } => DroppedPacket::Missing {
+                edge: connection,
+                argument,
+                property,
+                policy: policy.into(),
+            },
+            crate::model::DroppedPacket::Discarded { component, reason } => {
+                DroppedPacket::Discarded {
+                    node: component,
+                    reason,
+                }
+            }
+        }
+    }
 }
 
 #[allow(clippy::from_over_into)]
@@ -135,6 +602,7 @@ impl Into<crate::model::Connection> for Edge {
                 name: self.target_handle,
             },
             assignment: self.data.assignment,
+            missing: self.data.missing.into(),
         }
     }
 }
@@ -142,6 +610,164 @@ impl Into<crate::model::Connection> for Edge {
 pub type Argument = String;
 pub type PropertyName = String;
 pub type Assignment = std::collections::HashMap<Argument, PropertyName>;
+pub type PropertyTypes = std::collections::HashMap<PropertyName, PropertyType>;
+
+#[derive(Type, Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PropertyType {
+    #[default]
+    Any,
+    Text,
+    I64,
+    F64,
+    Bool,
+    Timestamp,
+    List,
+    Map,
+}
+
+impl From<crate::model::PropertyType> for PropertyType {
+    fn from(v: crate::model::PropertyType) -> PropertyType {
+        use crate::model::PropertyType::*;
+        match v {
+            Any => PropertyType::Any,
+            Text => PropertyType::Text,
+            I64 => PropertyType::I64,
+            F64 => PropertyType::F64,
+            Bool => PropertyType::Bool,
+            Timestamp => PropertyType::Timestamp,
+            List => PropertyType::List,
+            Map => PropertyType::Map,
+        }
+    }
+}
+
+#[allow(clippy::from_over_into)]
+impl Into<crate::model::PropertyType> for PropertyType {
+    fn into(self) -> crate::model::PropertyType {
+        use crate::model::PropertyType::*;
+        match self {
+            PropertyType::Any => Any,
+            PropertyType::Text => Text,
+            PropertyType::I64 => I64,
+            PropertyType::F64 => F64,
+            PropertyType::Bool => Bool,
+            PropertyType::Timestamp => Timestamp,
+            PropertyType::List => List,
+            PropertyType::Map => Map,
+        }
+    }
+}
+
+fn types_from(v: crate::model::PropertyTypes) -> PropertyTypes {
+    v.into_iter().map(|(k, t)| (k, t.into())).collect()
+}
+
+fn types_into(v: PropertyTypes) -> crate::model::PropertyTypes {
+    v.into_iter().map(|(k, t)| (k, t.into())).collect()
+}
+
+#[derive(Type, Debug, Default, Clone, Serialize, Deserialize)]
+pub struct AssignmentError {
+    pub edge: String,
+    pub argument: Argument,
+    pub property: PropertyName,
+    pub message: String,
+}
+
+impl AssignmentError {
+    /// wraps an error which is not about a specific assignment, such as failing to save.
+    pub fn other(e: impl std::fmt::Display) -> Vec<AssignmentError> {
+        vec![AssignmentError {
+            message: e.to_string(),
+            ..Default::default()
+        }]
+    }
+}
+
+impl From<crate::model::AssignmentError> for AssignmentError {
+    fn from(v: crate::model::AssignmentError) -> AssignmentError {
+        AssignmentError {
+            edge: v.connection,
+            argument: v.argument,
+            property: v.property,
+            message: v.message,
+        }
+    }
+}
+
+#[derive(Type, Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    #[default]
+    Error,
+    Warning,
+}
+
+#[derive(Type, Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IssueKind {
+    #[default]
+    Other,
+    InvalidSettings,
+    UnknownKind,
+    InvalidParams,
+    DuplicateId,
+    DanglingEdge,
+    UnknownPort,
+    UnassignedArgument,
+    IncompatibleAssignment,
+    Cycle,
+    Unconnected,
+}
+
+#[derive(Type, Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub kind: IssueKind,
+    pub node: Option<String>,
+    pub edge: Option<String>,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    /// wraps an error which is not about the pipeline itself, such as failing to save.
+    pub fn other(e: impl std::fmt::Display) -> Vec<ValidationIssue> {
+        vec![ValidationIssue {
+            message: e.to_string(),
+            ..Default::default()
+        }]
+    }
+}
+
+impl From<crate::model::ValidationIssue> for ValidationIssue {
+    fn from(v: crate::model::ValidationIssue) -> ValidationIssue {
+        use crate::model::IssueKind::*;
+        let kind = match v.kind {
+            InvalidSettings => IssueKind::InvalidSettings,
+            UnknownKind => IssueKind::UnknownKind,
+            InvalidParams => IssueKind::InvalidParams,
+            DuplicateId => IssueKind::DuplicateId,
+            DanglingEdge => IssueKind::DanglingEdge,
+            UnknownPort => IssueKind::UnknownPort,
+            UnassignedArgument => IssueKind::UnassignedArgument,
+            IncompatibleAssignment => IssueKind::IncompatibleAssignment,
+            Cycle => IssueKind::Cycle,
+            Unconnected => IssueKind::Unconnected,
+        };
+        let severity = match v.severity {
+            crate::model::Severity::Error => Severity::Error,
+            crate::model::Severity::Warning => Severity::Warning,
+        };
+        ValidationIssue {
+            severity,
+            kind,
+            node: v.component,
+            edge: v.connection,
+            message: v.message,
+        }
+    }
+}
 
 #[derive(Type, Debug, Default, Clone, Serialize, Deserialize)]
 pub struct InputPort {
@@ -149,6 +775,8 @@ pub struct InputPort {
     pub name: String,
     #[serde(rename = "propertyNames")]
     pub property_names: Vec<PropertyName>,
+    #[serde(default, rename = "propertyTypes")]
+    pub property_types: PropertyTypes,
 }
 
 impl From<crate::model::InputPort> for InputPort {
@@ -157,6 +785,7 @@ impl From<crate::model::InputPort> for InputPort {
             parent: v.id.parent,
             name: v.id.name,
             property_names: v.property_names,
+            property_types: types_from(v.property_types),
         }
     }
 }
@@ -170,6 +799,8 @@ impl Into<crate::model::InputPort> for InputPort {
                 name: self.name,
             },
             property_names: self.property_names,
+            property_types: types_into(self.property_types),
+            ..Default::default()
         }
     }
 }
@@ -180,6 +811,8 @@ pub struct OutputPort {
     pub name: String,
     #[serde(rename = "propertyNames")]
     pub property_names: Vec<PropertyName>,
+    #[serde(default, rename = "propertyTypes")]
+    pub property_types: PropertyTypes,
 }
 
 impl From<crate::model::OutputPort> for OutputPort {
@@ -188,6 +821,7 @@ impl From<crate::model::OutputPort> for OutputPort {
             parent: v.id.parent,
             name: v.id.name,
             property_names: v.property_names,
+            property_types: types_from(v.property_types),
         }
     }
 }
@@ -201,6 +835,7 @@ impl Into<crate::model::OutputPort> for OutputPort {
                 name: self.name,
             },
             property_names: self.property_names,
+            property_types: types_into(self.property_types),
         }
     }
 }
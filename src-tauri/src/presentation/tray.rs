@@ -1,6 +1,9 @@
+use std::sync::Mutex;
+
 use miette::{IntoDiagnostic, Result, WrapErr};
 
 use crate::model::error::MrDamianError;
+use crate::repository::Repositories;
 
 use tauri::{
     AppHandle, CustomMenuItem, Manager, SystemTrayEvent, SystemTrayMenu, SystemTrayMenuItem,
@@ -51,6 +54,21 @@ fn show_window(app: &AppHandle) -> Result<()> {
         .context("failed to change open window system tray menu")
 }
 
+/// stops the pipeline gracefully, then exits.
+fn quit(app: &AppHandle) {
+    let shutdown = app
+        .state::<Mutex<Repositories>>()
+        .lock()
+        .expect("Failed to lock pipeline repository")
+        .pipeline
+        .shutdown();
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        shutdown.await;
+        app.exit(0);
+    });
+}
+
 fn flip_window_visibility(app: &AppHandle) -> Result<()> {
     let window = app
         .get_window("main")
@@ -69,7 +87,7 @@ pub fn on_system_tray_event(app: &AppHandle, event: SystemTrayEvent) {
             flip_window_visibility(app).expect("failed to flip main window visibility.")
         }
         MenuItemClick { id, .. } => match id.as_str() {
-            "quit" => std::process::exit(0),
+            "quit" => quit(app),
             "hide" => hide_window(app).expect("failed to hide main window."),
             "open" => show_window(app).expect("failed to show main window."),
             _ => {}
@@ -0,0 +1,52 @@
+use std::fs;
+use std::path::PathBuf;
+
+use miette::{IntoDiagnostic, Result, WrapErr};
+
+use crate::config::Config;
+
+pub trait Repository {
+    /// stored settings as they were saved from the UI.
+    fn stored(&self) -> Config;
+    /// settings that the components should use, with environment overrides applied.
+    fn get(&self) -> Config {
+        self.stored().with_envs()
+    }
+    fn set(&mut self, updated: Config) -> Result<()>;
+}
+
+pub struct File {
+    config: Config,
+    path: PathBuf,
+}
+
+impl File {
+    pub fn load(path: PathBuf) -> Result<Self> {
+        let mut config = Config::default();
+        if path.exists() {
+            let content = fs::read_to_string(&path)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("failed to read settings file {}", path.display()))?;
+            config = serde_json::from_str(&content)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("failed to parse settings file {}", path.display()))?;
+        }
+        Ok(Self { config, path })
+    }
+
+    fn save(&self) -> Result<()> {
+        super::write_atomic(&self.path, &self.config)
+            .wrap_err_with(|| format!("failed to save settings file {}", self.path.display()))
+    }
+}
+
+impl Repository for File {
+    fn stored(&self) -> Config {
+        self.config.clone()
+    }
+
+    fn set(&mut self, updated: Config) -> Result<()> {
+        self.config = updated;
+        self.save()
+    }
+}
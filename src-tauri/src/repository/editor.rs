@@ -1,10 +1,16 @@
-use crate::presentation::protocol::{Assignment, Editor, Node};
+use std::fs;
+use std::path::PathBuf;
+
+use miette::{IntoDiagnostic, Result, WrapErr};
+
+use crate::presentation::protocol::{Assignment, Editor, MissingPolicy, Node};
 
 pub trait Repository {
     fn get(&self) -> Editor;
-    fn set(&mut self, updated: Editor);
+    fn set(&mut self, updated: Editor) -> Result<()>;
 
-    fn insert_node(&mut self, node: Node);
+    fn insert_node(&mut self, node: Node) -> Result<()>;
+    fn update_node(&mut self, node: Node) -> Result<()>;
 
     fn add_edge(
         &mut self,
@@ -12,15 +18,16 @@ pub trait Repository {
         target: String,
         source_handle: String,
         target_handle: String,
-    );
+    ) -> Result<()>;
     fn remove_edge(
         &mut self,
         source: String,
         target: String,
         source_handle: String,
         target_handle: String,
-    );
-    fn set_assignment(&mut self, id: String, assignment: Assignment);
+    ) -> Result<()>;
+    fn set_assignment(&mut self, id: String, assignment: Assignment) -> Result<()>;
+    fn set_missing_policy(&mut self, id: String, missing: MissingPolicy) -> Result<()>;
 }
 
 pub struct Impl {
@@ -40,12 +47,19 @@ impl Repository for Impl {
         self.editor.clone()
     }
 
-    fn set(&mut self, updated: Editor) {
+    fn set(&mut self, updated: Editor) -> Result<()> {
         self.editor = updated;
+        Ok(())
     }
 
-    fn insert_node(&mut self, node: Node) {
+    fn insert_node(&mut self, node: Node) -> Result<()> {
         self.editor.nodes.push(node);
+        Ok(())
+    }
+
+    fn update_node(&mut self, node: Node) -> Result<()> {
+        self.editor.update_node(node);
+        Ok(())
     }
 
     fn add_edge(
@@ -54,9 +68,10 @@ impl Repository for Impl {
         target: String,
         source_handle: String,
         target_handle: String,
-    ) {
+    ) -> Result<()> {
         self.editor
             .add_edge(source, target, source_handle, target_handle);
+        Ok(())
     }
 
     fn remove_edge(
@@ -65,12 +80,101 @@ impl Repository for Impl {
         target: String,
         source_handle: String,
         target_handle: String,
-    ) {
+    ) -> Result<()> {
         self.editor
             .remove_edge(source, target, source_handle, target_handle);
+        Ok(())
     }
 
-    fn set_assignment(&mut self, id: String, assignment: Assignment) {
+    fn set_assignment(&mut self, id: String, assignment: Assignment) -> Result<()> {
         self.editor.set_assignment(id, assignment);
+        Ok(())
+    }
+
+    fn set_missing_policy(&mut self, id: String, missing: MissingPolicy) -> Result<()> {
+        self.editor.set_missing_policy(id, missing);
+        Ok(())
+    }
+}
+
+/// File keeps the editor in memory like `Impl`, and writes it out as JSON
+/// after every mutation so the node graph survives restarts.
+pub struct File {
+    inner: Impl,
+    path: PathBuf,
+}
+
+impl File {
+    pub fn load(path: PathBuf) -> Result<Self> {
+        let mut inner = Impl::new();
+        if path.exists() {
+            let content = fs::read_to_string(&path)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("failed to read editor file {}", path.display()))?;
+            inner.editor = serde_json::from_str(&content)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("failed to parse editor file {}", path.display()))?;
+        }
+        Ok(Self { inner, path })
+    }
+
+    fn save(&self) -> Result<()> {
+        super::write_atomic(&self.path, &self.inner.editor)
+            .wrap_err_with(|| format!("failed to save editor file {}", self.path.display()))
+    }
+}
+
+impl Repository for File {
+    fn get(&self) -> Editor {
+        self.inner.get()
+    }
+
+    fn set(&mut self, updated: Editor) -> Result<()> {
+        self.inner.set(updated)?;
+        self.save()
+    }
+
+    fn insert_node(&mut self, node: Node) -> Result<()> {
+        self.inner.insert_node(node)?;
+        self.save()
+    }
+
+    fn update_node(&mut self, node: Node) -> Result<()> {
+        self.inner.update_node(node)?;
+        self.save()
+    }
+
+    fn add_edge(
+        &mut self,
+        source: String,
+        target: String,
+        source_handle: String,
+        target_handle: String,
+    ) -> Result<()> {
+        self.inner
+            .add_edge(source, target, source_handle, target_handle)?;
+        self.save()
+    }
+
+    fn remove_edge(
+        &mut self,
+        source: String,
+        target: String,
+        source_handle: String,
+        target_handle: String,
+    ) -> Result<()> {
+        self.inner
+            .remove_edge(source, target, source_handle, target_handle)?;
+        self.save()
+    }
+
+    fn set_assignment(&mut self, id: String, assignment: Assignment) -> Result<()> {
+        self.inner.set_assignment(id, assignment)?;
+        self.save()
+    }
+
+    fn set_missing_policy(&mut self, id: String, missing: MissingPolicy) -> Result<()> {
+        self.inner.set_missing_policy(id, missing)?;
+        self.save()
     }
 }
@@ -0,0 +1,255 @@
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use miette::{IntoDiagnostic, Result};
+use tokio::sync::broadcast;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+use crate::model::{LogEntry, LogLevel};
+
+const LOG_FILE: &str = "mrdamian.log";
+/// daily log files kept in the directory, the older ones are removed.
+const MAX_LOG_FILES: usize = 14;
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// entries kept in memory for the log panel. older ones are only in the log files.
+const CAPACITY: usize = 2000;
+
+/// Logs keeps the recent log entries and broadcasts new ones,
+/// as a `tracing` layer installed by `init`.
+#[derive(Debug, Clone)]
+pub struct Logs {
+    entries: Arc<Mutex<VecDeque<LogEntry>>>,
+    sender: broadcast::Sender<LogEntry>,
+}
+
+impl Logs {
+    /// installs the global subscriber, which writes to stderr,
+    /// to the daily rotated JSON files in `dir`, and to the returned `Logs`.
+    /// the panel gets INFO and above, while the files get DEBUG too.
+    pub fn init(dir: &Path) -> Result<Self> {
+        let logs = Self::new();
+        let filter = Targets::new()
+            .with_target(env!("CARGO_PKG_NAME"), LevelFilter::DEBUG)
+            .with_default(LevelFilter::WARN);
+        let file = tracing_appender::rolling::daily(dir, LOG_FILE);
+        // tracing-appender 0.2.2 cannot limit the number of files by itself.
+        let prune_dir = dir.to_path_buf();
+        std::thread::spawn(move || loop {
+            if let Err(err) = prune(&prune_dir, MAX_LOG_FILES) {
+                tracing::warn!("failed to remove old log files: {}", err);
+            }
+            std::thread::sleep(PRUNE_INTERVAL);
+        });
+
+        tracing_subscriber::registry()
+            .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .json()
+                    .with_ansi(false)
+                    .with_writer(file),
+            )
+            .with(logs.clone().with_filter(LevelFilter::INFO))
+            .with(filter)
+            .try_init()
+            .into_diagnostic()?;
+        Ok(logs)
+    }
+
+    fn new() -> Self {
+        let (sender, _) = broadcast::channel(256);
+        Self {
+            entries: Arc::default(),
+            sender,
+        }
+    }
+
+    /// recent entries, only of the component if it is given.
+    pub fn entries(&self, component: Option<&str>) -> Vec<LogEntry> {
+        let entries = self.entries.lock().expect("Failed to lock logs");
+        entries
+            .iter()
+            .filter(|e| component.is_none() || e.component.as_deref() == component)
+            .cloned()
+            .collect()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<LogEntry> {
+        self.sender.subscribe()
+    }
+
+    fn append(&self, entry: LogEntry) {
+        {
+            let mut entries = self.entries.lock().expect("Failed to lock logs");
+            if entries.len() == CAPACITY {
+                entries.pop_front();
+            }
+            entries.push_back(entry.clone());
+        }
+        // sending only fails when there is no subscriber.
+        let _ = self.sender.send(entry);
+    }
+}
+
+/// removes the oldest rotated log files, keeping the newest `keep` ones.
+fn prune(dir: &Path, keep: usize) -> Result<()> {
+    let prefix = format!("{}.", LOG_FILE);
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_diagnostic()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .map_or(false, |name| name.starts_with(&prefix))
+        })
+        .collect();
+    // the files are suffixed by the date, so the names sort by age.
+    files.sort();
+    let excess = files.len().saturating_sub(keep);
+    for path in &files[..excess] {
+        std::fs::remove_file(path).into_diagnostic()?;
+    }
+    Ok(())
+}
+
+/// fields of a span which identify the component.
+#[derive(Debug, Default)]
+struct SpanFields {
+    component: Option<String>,
+    kind: Option<String>,
+}
+
+impl Visit for SpanFields {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.record_str(field, &format!("{:?}", value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "component" => self.component = Some(value.to_string()),
+            "kind" => self.kind = Some(value.to_string()),
+            _ => (),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct EventFields {
+    message: String,
+    /// the component the event is about, when it is logged outside of the process.
+    component: Option<String>,
+    fields: Vec<(String, String)>,
+}
+
+impl Visit for EventFields {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.record_str(field, &format!("{:?}", value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "message" => self.message = value.to_string(),
+            "component" => self.component = Some(value.to_string()),
+            name => self.fields.push((name.to_string(), value.to_string())),
+        }
+    }
+}
+
+impl<S> Layer<S> for Logs
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut fields = SpanFields::default();
+        attrs.record(&mut fields);
+        if fields.component.is_some() {
+            if let Some(span) = ctx.span(id) {
+                span.extensions_mut().insert(fields);
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut fields = EventFields::default();
+        event.record(&mut fields);
+
+        let mut entry = LogEntry {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default(),
+            level: level(event.metadata().level()),
+            target: event.metadata().target().to_string(),
+            component: fields.component,
+            kind: None,
+            message: fields.message,
+            fields: fields.fields,
+        };
+        // the innermost span with the component wins.
+        for span in ctx.event_scope(event).into_iter().flatten() {
+            if let Some(f) = span.extensions().get::<SpanFields>() {
+                entry.component = f.component.clone();
+                entry.kind = f.kind.clone();
+                break;
+            }
+        }
+        self.append(entry);
+    }
+}
+
+fn level(level: &Level) -> LogLevel {
+    match *level {
+        Level::TRACE => LogLevel::Trace,
+        Level::DEBUG => LogLevel::Debug,
+        Level::INFO => LogLevel::Info,
+        Level::WARN => LogLevel::Warn,
+        Level::ERROR => LogLevel::Error,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_prune() {
+        let dir = std::env::temp_dir().join(ulid::Ulid::new().to_string());
+        std::fs::create_dir_all(&dir).unwrap();
+        let names = [
+            "mrdamian.log.2023-05-01",
+            "mrdamian.log.2023-05-03",
+            "mrdamian.log.2023-05-02",
+            "mrdamian.log.2023-04-30",
+            "other.json",
+        ];
+        for name in names {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+
+        prune(&dir, 2).unwrap();
+        let mut left: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        left.sort();
+        assert_eq!(
+            left,
+            vec![
+                "mrdamian.log.2023-05-02",
+                "mrdamian.log.2023-05-03",
+                "other.json"
+            ]
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,19 +1,157 @@
+mod config;
 mod editor;
+pub mod log;
 mod pipeline;
 
+use miette::{IntoDiagnostic, Result};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::model::{Pipeline, Severity, ValidationIssue};
+use crate::operation::factory;
+use crate::operation::pipeline::{Context, Monitor};
+use crate::operation::twitch::{EventSubHub, OAuth, TokenProvider};
+
+const CONFIG_FILE: &str = "settings.json";
+const CREDENTIALS_FILE: &str = "credentials.json";
+const EDITOR_FILE: &str = "editor.json";
+const LOG_DIR: &str = "logs";
+
 pub struct Repositories {
+    pub config: Box<dyn config::Repository + Send>,
     pub editor: Box<dyn editor::Repository + Send>,
     pub pipeline: Box<dyn pipeline::Repository + Send>,
+    pub auth: TokenProvider,
+    pub monitor: Monitor,
+    pub eventsub: EventSubHub,
+    pub logs: log::Logs,
 }
 
 impl Repositories {
-    pub fn new() -> Self {
-        Self {
-            editor: Box::new(editor::Impl::new()),
+    pub fn new(data_dir: &Path) -> Result<Self> {
+        // set up first to log what happens while loading the others.
+        let logs = log::Logs::init(&data_dir.join(LOG_DIR))?;
+        let oauth = OAuth::default();
+        Ok(Self {
+            config: Box::new(recover(data_dir.join(CONFIG_FILE), config::File::load)?),
+            editor: Box::new(recover(data_dir.join(EDITOR_FILE), editor::File::load)?),
             pipeline: Box::new(pipeline::Impl::new()),
+            auth: recover(data_dir.join(CREDENTIALS_FILE), |path| {
+                TokenProvider::load(oauth.clone(), path)
+            })?,
+            monitor: Monitor::new(),
+            eventsub: EventSubHub::default(),
+            logs,
+        })
+    }
+
+    pub fn context(&self) -> Context {
+        let config = self.config.get();
+        let auth = if config.token.is_empty() {
+            self.auth.clone()
+        } else {
+            TokenProvider::from_access_token(self.auth.oauth().clone(), &config.token)
+        };
+        Context {
+            config,
+            auth,
+            monitor: self.monitor.clone(),
+            eventsub: self.eventsub.clone(),
         }
     }
 
+    /// (re)deploy the running pipeline from the persisted editor and settings.
+    /// returns the issues which keep it from being deployed.
+    pub fn restore(&mut self) -> Vec<ValidationIssue> {
+        let pipeline: Pipeline = self.editor.get().into();
+        let ctx = self.context();
+        let issues = factory().validate(&pipeline, &ctx);
+        let errors: Vec<ValidationIssue> = issues
+            .into_iter()
+            .filter(|i| i.severity == Severity::Error)
+            .collect();
+        if !errors.is_empty() {
+            for issue in &errors {
+                tracing::error!(
+                    component = issue.component,
+                    connection = issue.connection,
+                    "invalid pipeline: {}",
+                    issue.message
+                );
+            }
+            return errors;
+        }
+        self.pipeline.restart(pipeline, &ctx);
+        vec![]
+    }
+
     // TODO:
     // pub fn mock(&self) -> Self { .. }
 }
+
+/// loads the file, or moves it aside to start with the defaults when it cannot be loaded,
+/// since a broken file should not keep the app from launching.
+fn recover<T>(path: PathBuf, load: impl Fn(PathBuf) -> Result<T>) -> Result<T> {
+    load(path.clone()).or_else(|err| {
+        let corrupt = path.with_extension("json.corrupt");
+        fs::rename(&path, &corrupt).into_diagnostic()?;
+        tracing::error!(
+            "moved the broken file to {}, starting with the defaults: {:?}",
+            corrupt.display(),
+            err
+        );
+        load(path)
+    })
+}
+
+/// writes the value as JSON into a temporary file first, and then moves it to `path`,
+/// so that a crash never leaves a truncated file.
+pub fn write_atomic<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).into_diagnostic()?;
+    }
+    let content = serde_json::to_string_pretty(value).into_diagnostic()?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, content).into_diagnostic()?;
+    fs::rename(&tmp, path).into_diagnostic()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use config::Repository as _;
+
+    #[test]
+    fn recover_corrupt_file() {
+        let dir = std::env::temp_dir().join(format!("mrdamian-{}", ulid::Ulid::new()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(CONFIG_FILE);
+        fs::write(&path, "{ broken").unwrap();
+
+        let loaded = recover(path.clone(), config::File::load).unwrap();
+        assert_eq!(loaded.stored(), crate::config::Config::default());
+        assert!(!path.exists());
+        assert_eq!(
+            fs::read_to_string(dir.join("settings.json.corrupt")).unwrap(),
+            "{ broken"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = std::env::temp_dir().join(format!("mrdamian-{}", ulid::Ulid::new()));
+        let path = dir.join(CONFIG_FILE);
+        write_atomic(&path, &vec!["old"]).unwrap();
+        write_atomic(&path, &vec!["new"]).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Vec<String>>(&content).unwrap(),
+            ["new"]
+        );
+        assert!(!path.with_extension("json.tmp").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,9 +1,22 @@
-use crate::model::Pipeline;
-use crate::operation::{factory, pipeline::Handles};
+use crate::model::{Health, Pipeline};
+use crate::operation::{
+    factory,
+    pipeline::{Context, Handles, Shutdown, Wiring},
+    stop,
+};
 
 pub trait Repository {
     fn get(&self) -> Pipeline;
-    fn set(&mut self, updated: Pipeline);
+    /// applies the updated pipeline, keeping the processes of unchanged components.
+    fn set(&mut self, updated: Pipeline, ctx: &Context);
+    /// restarts all processes, e.g. when the context is changed.
+    fn restart(&mut self, updated: Pipeline, ctx: &Context);
+    /// the status of the process of each deployed component.
+    fn health(&self) -> Vec<Health>;
+    /// the wiring of the running process of the component.
+    fn wiring(&self, id: &str) -> Option<Wiring>;
+    /// stops all processes, e.g. when the app quits.
+    fn shutdown(&mut self) -> Shutdown;
 }
 
 pub struct Impl {
@@ -13,10 +26,10 @@ pub struct Impl {
 
 impl Impl {
     pub fn new() -> Self {
-        let pipeline = Pipeline::default();
-        let handles = factory().create_pipeline(&pipeline);
-
-        Self { pipeline, handles }
+        Self {
+            pipeline: Pipeline::default(),
+            handles: Handles::default(),
+        }
     }
 }
 
@@ -25,8 +38,28 @@ impl Repository for Impl {
         self.pipeline.clone()
     }
 
-    fn set(&mut self, updated: Pipeline) {
-        self.handles = factory().create_pipeline(&updated);
+    fn set(&mut self, updated: Pipeline, ctx: &Context) {
+        factory().update_pipeline(&mut self.handles, &self.pipeline, &updated, ctx);
         self.pipeline = updated;
     }
+
+    fn restart(&mut self, updated: Pipeline, ctx: &Context) {
+        // the old processes drain in the background while the new ones start.
+        stop(self.handles.take(), &self.pipeline);
+        self.handles = factory().create_pipeline(&updated, ctx);
+        self.pipeline = updated;
+    }
+
+    fn health(&self) -> Vec<Health> {
+        self.handles.health()
+    }
+
+    fn wiring(&self, id: &str) -> Option<Wiring> {
+        self.handles.get(id).map(|h| h.wiring.clone())
+    }
+
+    fn shutdown(&mut self) -> Shutdown {
+        let upstreams = self.pipeline.upstreams();
+        Box::pin(self.handles.take().shutdown(upstreams))
+    }
 }
@@ -1,4 +1,4 @@
-import { useState, useCallback } from 'react';
+import { useState, useCallback, useEffect } from 'react';
 import ReactFlow, {
   MiniMap,
   Controls,
@@ -9,21 +9,33 @@ import ReactFlow, {
 import 'reactflow/dist/style.css';
 
 import { Button } from 'semantic-ui-react';
+import { listen } from '@tauri-apps/api/event';
 
 import { usePipeline } from './pipeline';
 
 import {
+  AssignmentError,
+  Candidate,
   Edge,
   InputPort,
+  MissingPolicy,
   OutputPort,
   Position,
+  ValidationIssue,
   createComponent,
   setAssignment,
+  setMissingPolicy,
   addEdge,
   removeEdge,
+  candidates,
 } from './bindings';
+import { CandidatesContext } from './candidates';
+import { HealthContext } from './health';
+import { ScopesContext, useScopes } from './scopes';
 import { ContextMenu } from './ContextMenu';
 import { AssignmentModal } from './AssignmentModal';
+import { LoginModal } from './LoginModal';
+import { SettingsModal } from './SettingsModal';
 
 export type ContextMenuState = {
   open: boolean;
@@ -40,7 +52,34 @@ type AssignModalState = {
 
 function App() {
   const [modal, setModal] = useState<AssignModalState>({ open: false });
-  const { onApply, ...pipeline } = usePipeline({
+  const [settingsOpen, setSettingsOpen] = useState(false);
+  const [loginOpen, setLoginOpen] = useState(false);
+  const scopes = useScopes();
+  const [cands, setCands] = useState<Candidate[]>([]);
+  useEffect(() => {
+    (async () => {
+      setCands(await candidates());
+    })();
+  }, []);
+
+  // the saved pipeline is deployed again when the settings or the login change.
+  useEffect(() => {
+    let unlisten: () => void = () => ({});
+    (async () => {
+      unlisten = await listen<ValidationIssue[]>(
+        'pipeline-rejected',
+        ({ payload }) => {
+          const details = payload.map((i) => i.message).join('\n');
+          alert(`The pipeline was not deployed.\n${details}`);
+        },
+      );
+    })();
+    return () => {
+      unlisten();
+    };
+  }, []);
+
+  const { onApply, health, ...pipeline } = usePipeline({
     onAssignEdit: (edge: Edge, source: OutputPort, target: InputPort) => {
       setModal({ open: true, edge, source, target });
     },
@@ -63,9 +102,18 @@ function App() {
   });
 
   const onAssign = useCallback(
-    (id: string, assignment: Record<string, string>) => {
-      setAssignment(id, assignment);
-      setModal({ open: false });
+    async (
+      id: string,
+      assignment: Record<string, string>,
+      missing: MissingPolicy,
+    ) => {
+      try {
+        await setAssignment(id, assignment);
+        await setMissingPolicy(id, missing);
+        setModal({ open: false });
+      } catch (errors) {
+        alert((errors as AssignmentError[]).map((e) => e.message).join('\n'));
+      }
     },
     [],
   );
@@ -85,34 +133,60 @@ function App() {
     setMenu({ open: false, x: 0, y: 0 });
   }, [setMenu]);
   const onMenuClick = useCallback(async (type: string, pos: Position) => {
-    await createComponent(type, pos);
+    try {
+      await createComponent(type, pos);
+    } catch (error) {
+      alert(`The node was not created.\n${error}`);
+    }
   }, []);
 
   return (
-    <div className="container">
-      <ReactFlow
-        {...pipeline}
-        fitView={true}
-        onPaneContextMenu={onPaneContextMenu}
-      >
-        <MiniMap />
-        <Controls />
-        <Background />
-      </ReactFlow>
-      <Button onClick={onApply} primary>
-        Apply
-      </Button>
-      <ContextMenu
-        {...menu}
-        onMenuClose={onMenuClose}
-        onMenuClick={onMenuClick}
-      />
-      <AssignmentModal
-        {...modal}
-        onAssign={onAssign}
-        onDiscard={() => setModal({ open: false })}
-      />
-    </div>
+    <CandidatesContext.Provider value={cands}>
+      <HealthContext.Provider value={health}>
+        <ScopesContext.Provider value={scopes}>
+          <div className="container">
+            <ReactFlow
+              {...pipeline}
+              fitView={true}
+              onPaneContextMenu={onPaneContextMenu}
+            >
+              <MiniMap />
+              <Controls />
+              <Background />
+            </ReactFlow>
+            <Button.Group>
+              <Button onClick={() => setSettingsOpen(true)} secondary>
+                Settings
+              </Button>
+              <Button onClick={() => setLoginOpen(true)} secondary>
+                Login
+              </Button>
+              <Button onClick={onApply} primary>
+                Apply
+              </Button>
+            </Button.Group>
+            <ContextMenu
+              {...menu}
+              onMenuClose={onMenuClose}
+              onMenuClick={onMenuClick}
+            />
+            <AssignmentModal
+              {...modal}
+              onAssign={onAssign}
+              onDiscard={() => setModal({ open: false })}
+            />
+            <SettingsModal
+              open={settingsOpen}
+              onClose={() => setSettingsOpen(false)}
+            />
+            <LoginModal
+              open={loginOpen}
+              onClose={() => setLoginOpen(false)}
+            />
+          </div>
+        </ScopesContext.Provider>
+      </HealthContext.Provider>
+    </CandidatesContext.Provider>
   );
 }
 
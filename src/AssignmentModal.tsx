@@ -3,7 +3,18 @@ import 'reactflow/dist/style.css';
 
 import { Button, Modal, Table, Select } from 'semantic-ui-react';
 
-import { Edge, InputPort, OutputPort } from './bindings';
+import {
+  Edge,
+  InputPort,
+  MissingPolicy,
+  OutputPort,
+  PropertyType,
+} from './bindings';
+import { TrafficPanel } from './TrafficPanel';
+
+// mirrors `PropertyType::assignable_to` on the backend.
+const assignable = (from: PropertyType, to: PropertyType) =>
+  from === to || from === 'any' || to === 'any';
 
 type Prop = {
   open: boolean;
@@ -11,7 +22,11 @@ type Prop = {
   source?: OutputPort;
   target?: InputPort;
 
-  onAssign: (id: string, assignment: Record<string, string>) => void;
+  onAssign: (
+    id: string,
+    assignment: Record<string, string>,
+    missing: MissingPolicy,
+  ) => void;
   onDiscard: () => void;
 };
 
@@ -26,10 +41,13 @@ export const AssignmentModal: React.FC<Prop> = ({
   const [assignment, setAssignment] = useState<Record<string, string>>(
     edge?.data.assignment ?? {},
   );
+  const [missing, setMissing] = useState<MissingPolicy>(
+    edge?.data.missing ?? 'skip',
+  );
   const onClose = onDiscard;
   const onApply = useCallback(
-    () => onAssign(edge?.id ?? '', assignment),
-    [edge, onAssign, assignment],
+    () => onAssign(edge?.id ?? '', assignment, missing),
+    [edge, onAssign, assignment, missing],
   );
 
   return (
@@ -47,16 +65,23 @@ export const AssignmentModal: React.FC<Prop> = ({
           <Table.Body>
             {target?.propertyNames.map((prop: string) => (
               <Table.Row key={prop}>
-                <Table.Cell>{prop}</Table.Cell>
+                <Table.Cell>
+                  {prop}: {target?.propertyTypes[prop] ?? 'any'}
+                </Table.Cell>
                 <Table.Cell>
                   <Select
                     value={edge?.data.assignment[prop]}
                     options={
-                      source?.propertyNames.map((prop: string) => ({
-                        key: prop,
-                        value: prop,
-                        text: prop,
-                      })) ?? []
+                      source?.propertyNames.map((name: string) => {
+                        const from = source?.propertyTypes[name] ?? 'any';
+                        const to = target?.propertyTypes[prop] ?? 'any';
+                        return {
+                          key: name,
+                          value: name,
+                          text: `${name}: ${from}`,
+                          disabled: !assignable(from, to),
+                        };
+                      }) ?? []
                     }
                     onChange={(_, { value }) => {
                       setAssignment({
@@ -70,6 +95,24 @@ export const AssignmentModal: React.FC<Prop> = ({
             ))}
           </Table.Body>
         </Table>
+        <Select
+          value={missing}
+          options={[
+            { key: 'skip', value: 'skip', text: 'Skip messages lacking a property' },
+            {
+              key: 'fillDefault',
+              value: 'fillDefault',
+              text: 'Fill missing properties with defaults',
+            },
+            {
+              key: 'error',
+              value: 'error',
+              text: 'Send messages lacking a property to the error port',
+            },
+          ]}
+          onChange={(_, { value }) => setMissing(value as MissingPolicy)}
+        />
+        {open && edge && <TrafficPanel edge={edge.id} />}
       </Modal.Content>
       <Modal.Actions>
         <Button onClick={onClose} secondary>
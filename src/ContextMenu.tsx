@@ -1,6 +1,7 @@
-import { useState, useEffect } from 'react';
+import { useContext } from 'react';
 import { Popup, Menu } from 'semantic-ui-react';
-import { Position, Candidate, candidates } from './bindings';
+import { Position } from './bindings';
+import { CandidatesContext } from './candidates';
 
 export type ContextMenuProps = {
   open: boolean;
@@ -18,14 +19,7 @@ export const ContextMenu: React.FC<ContextMenuProps> = ({
   onMenuClose,
   onMenuClick,
 }) => {
-  const [cands, setCands] = useState<Candidate[]>([]);
-
-  useEffect(() => {
-    (async () => {
-      const cands = await candidates();
-      setCands(cands);
-    })();
-  }, []);
+  const cands = useContext(CandidatesContext);
 
   return (
     <Popup
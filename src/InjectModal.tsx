@@ -0,0 +1,87 @@
+import { useCallback, useEffect, useState } from 'react';
+
+import { Button, Form, Modal } from 'semantic-ui-react';
+
+import { InjectPreset, Property, inject, injectPresets } from './bindings';
+
+type Prop = {
+  open: boolean;
+  node: string;
+  label: string;
+  onClose: () => void;
+};
+
+export const InjectModal: React.FC<Prop> = ({ open, node, label, onClose }) => {
+  const [presets, setPresets] = useState<InjectPreset[]>([]);
+  const [port, setPort] = useState('');
+  const [text, setText] = useState('');
+
+  const selectPreset = useCallback((preset?: InjectPreset) => {
+    setPort(preset?.port ?? '');
+    setText(JSON.stringify(preset?.message ?? {}, null, 2));
+  }, []);
+
+  useEffect(() => {
+    if (!open) return;
+    (async () => {
+      const presets = await injectPresets(node);
+      setPresets(presets);
+      selectPreset(presets[0]);
+    })();
+  }, [open, node, selectPreset]);
+
+  const onInject = useCallback(async () => {
+    let message: Record<string, Property>;
+    try {
+      message = JSON.parse(text);
+    } catch (e) {
+      alert(`The message is not valid JSON: ${e}`);
+      return;
+    }
+    try {
+      await inject(node, port, message);
+    } catch (e) {
+      alert(e);
+    }
+  }, [node, port, text]);
+
+  return (
+    <Modal open={open} onClose={onClose}>
+      <Modal.Header>Inject a message from {label}</Modal.Header>
+      <Modal.Content>
+        {presets.length === 0 ? (
+          <p>The node is not deployed, or has no output ports.</p>
+        ) : (
+          <Form>
+            <Form.Select
+              label="Port"
+              value={port}
+              options={presets.map((p) => ({
+                key: p.port,
+                value: p.port,
+                text: p.port,
+              }))}
+              onChange={(_, { value }) =>
+                selectPreset(presets.find((p) => p.port === value))
+              }
+            />
+            <Form.TextArea
+              label="Message"
+              rows={12}
+              value={text}
+              onChange={(_, { value }) => setText(String(value))}
+            />
+          </Form>
+        )}
+      </Modal.Content>
+      <Modal.Actions>
+        <Button onClick={onClose} secondary>
+          Close
+        </Button>
+        <Button onClick={onInject} disabled={port === ''} primary>
+          Inject
+        </Button>
+      </Modal.Actions>
+    </Modal>
+  );
+};
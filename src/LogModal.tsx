@@ -0,0 +1,96 @@
+import { useEffect, useMemo, useState } from 'react';
+
+import { Button, Input, Modal, Select, Table } from 'semantic-ui-react';
+import { listen } from '@tauri-apps/api/event';
+
+import { LogEntry, LogLevel, logs } from './bindings';
+
+const LEVELS: LogLevel[] = ['trace', 'debug', 'info', 'warn', 'error'];
+
+const LEVEL_COLORS: Record<LogLevel, string> = {
+  trace: '#767676',
+  debug: '#767676',
+  info: '#000',
+  warn: '#f2711c',
+  error: '#db2828',
+};
+
+// entries kept in the panel, same as the backend keeps.
+const CAPACITY = 2000;
+
+type Prop = {
+  open: boolean;
+  node: string;
+  label: string;
+  onClose: () => void;
+};
+
+export const LogModal: React.FC<Prop> = ({ open, node, label, onClose }) => {
+  const [entries, setEntries] = useState<LogEntry[]>([]);
+  const [level, setLevel] = useState<LogLevel>('info');
+  const [text, setText] = useState('');
+
+  useEffect(() => {
+    if (!open) return;
+    let unlisten: () => void = () => ({});
+    (async () => {
+      setEntries(await logs(node));
+      unlisten = await listen<LogEntry>('log-appended', ({ payload }) => {
+        if (payload.node !== node) return;
+        setEntries((entries) => [...entries, payload].slice(-CAPACITY));
+      });
+    })();
+    return () => {
+      unlisten();
+    };
+  }, [open, node]);
+
+  const filtered = useMemo(() => {
+    const min = LEVELS.indexOf(level);
+    return entries.filter(
+      (e) =>
+        LEVELS.indexOf(e.level) >= min &&
+        (text === '' || e.message.includes(text)),
+    );
+  }, [entries, level, text]);
+
+  return (
+    <Modal open={open} onClose={onClose}>
+      <Modal.Header>Logs of {label}</Modal.Header>
+      <Modal.Content scrolling>
+        <Select
+          value={level}
+          options={LEVELS.map((l) => ({ key: l, value: l, text: l }))}
+          onChange={(_, { value }) => setLevel(value as LogLevel)}
+        />
+        <Input
+          icon="search"
+          placeholder="Filter"
+          value={text}
+          onChange={(_, { value }) => setText(value)}
+        />
+        <Table compact size="small">
+          <Table.Body>
+            {filtered.map((e, i) => (
+              <Table.Row key={i} style={{ color: LEVEL_COLORS[e.level] }}>
+                <Table.Cell collapsing>
+                  {new Date(e.timestamp).toLocaleTimeString()}
+                </Table.Cell>
+                <Table.Cell collapsing>{e.level}</Table.Cell>
+                <Table.Cell>
+                  {e.message}
+                  {Object.entries(e.fields).map(([k, v]) => ` ${k}=${v}`)}
+                </Table.Cell>
+              </Table.Row>
+            ))}
+          </Table.Body>
+        </Table>
+      </Modal.Content>
+      <Modal.Actions>
+        <Button onClick={onClose} secondary>
+          Close
+        </Button>
+      </Modal.Actions>
+    </Modal>
+  );
+};
@@ -0,0 +1,142 @@
+import { useCallback, useEffect, useState } from 'react';
+
+import { Button, Label, Message, Modal, Segment } from 'semantic-ui-react';
+import { listen } from '@tauri-apps/api/event';
+import { open as openUrl } from '@tauri-apps/api/shell';
+
+import {
+  AuthStatus,
+  DeviceLogin,
+  authStatus,
+  login,
+  logout,
+} from './bindings';
+
+// the approval is notified by `auth-updated`, and polling covers a missed event.
+const POLL_INTERVAL = 5000;
+
+type Prop = {
+  open: boolean;
+  onClose: () => void;
+};
+
+export const LoginModal: React.FC<Prop> = ({ open, onClose }) => {
+  const [status, setStatus] = useState<AuthStatus>();
+  const [pending, setPending] = useState<DeviceLogin>();
+  const [error, setError] = useState<string>();
+
+  const refresh = useCallback(async () => {
+    const status = await authStatus();
+    setStatus(status);
+    if (status.loggedIn) setPending(undefined);
+  }, []);
+
+  useEffect(() => {
+    if (!open) return;
+    refresh();
+    const unlistens: (() => void)[] = [];
+    (async () => {
+      unlistens.push(
+        await listen('auth-updated', () => {
+          setError(undefined);
+          refresh();
+        }),
+      );
+      unlistens.push(
+        await listen<string>('auth-failed', ({ payload }) => {
+          setPending(undefined);
+          setError(payload);
+        }),
+      );
+    })();
+    return () => unlistens.forEach((unlisten) => unlisten());
+  }, [open, refresh]);
+
+  useEffect(() => {
+    if (!open || !pending) return;
+    const timer = setInterval(refresh, POLL_INTERVAL);
+    // the code is useless after it expires.
+    const expiry = setTimeout(() => {
+      setPending(undefined);
+      setError('The code has expired. Please try again.');
+    }, pending.expiresIn * 1000);
+    return () => {
+      clearInterval(timer);
+      clearTimeout(expiry);
+    };
+  }, [open, pending, refresh]);
+
+  const onLogin = useCallback(async () => {
+    setError(undefined);
+    try {
+      const code = await login();
+      setPending(code);
+      await openUrl(code.verificationUri);
+    } catch (error) {
+      setError(String(error));
+    }
+  }, []);
+
+  const onLogout = useCallback(async () => {
+    setError(undefined);
+    try {
+      await logout();
+    } catch (error) {
+      setError(String(error));
+    }
+  }, []);
+
+  return (
+    <Modal open={open} onClose={onClose} size="small">
+      <Modal.Header>Twitch Login</Modal.Header>
+      <Modal.Content>
+        {status?.loggedIn ? (
+          <Segment>
+            Logged in as <b>{status.login}</b>
+            <div>
+              {status.scopes.map((scope) => (
+                <Label key={scope} size="mini">
+                  {scope}
+                </Label>
+              ))}
+            </div>
+          </Segment>
+        ) : pending ? (
+          <Segment textAlign="center">
+            <p>
+              Open{' '}
+              <a href="#" onClick={() => openUrl(pending.verificationUri)}>
+                {pending.verificationUri}
+              </a>{' '}
+              and enter the code below.
+            </p>
+            <h2>{pending.userCode}</h2>
+            <p>Waiting for the approval...</p>
+          </Segment>
+        ) : (
+          <Segment>Not logged in.</Segment>
+        )}
+        {error && <Message error content={error} />}
+      </Modal.Content>
+      <Modal.Actions>
+        <Button onClick={onClose} secondary>
+          Close
+        </Button>
+        {status?.loggedIn ? (
+          <Button onClick={onLogout} negative>
+            Logout
+          </Button>
+        ) : (
+          <Button
+            onClick={onLogin}
+            primary
+            loading={!!pending}
+            disabled={!!pending}
+          >
+            Login
+          </Button>
+        )}
+      </Modal.Actions>
+    </Modal>
+  );
+};
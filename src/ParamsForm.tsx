@@ -0,0 +1,73 @@
+import { useState } from 'react';
+import { Form } from 'semantic-ui-react';
+
+import { ParamSpec, ParamValue } from './bindings';
+
+const ParamInput: React.FC<{
+  spec: ParamSpec;
+  value: ParamValue;
+  onChange: (value: ParamValue) => void;
+}> = ({ spec, value, onChange }) => {
+  const [text, setText] = useState(String(value));
+
+  switch (spec.type) {
+    case 'multiline':
+      return (
+        <Form.TextArea
+          className="nodrag"
+          label={spec.label}
+          value={text}
+          onChange={(_, { value }) => setText(String(value))}
+          onBlur={() => onChange(text)}
+        />
+      );
+    case 'integer':
+      return (
+        <Form.Input
+          className="nodrag"
+          type="number"
+          label={spec.label}
+          value={text}
+          onChange={(_, { value }) => setText(value)}
+          onBlur={() => onChange(Number.parseInt(text, 10) || 0)}
+        />
+      );
+    case 'choice':
+      return (
+        <Form.Select
+          className="nodrag"
+          label={spec.label}
+          value={String(value)}
+          options={spec.options.map((o) => ({ key: o, value: o, text: o }))}
+          onChange={(_, { value }) => onChange(String(value))}
+        />
+      );
+    default:
+      return (
+        <Form.Input
+          className="nodrag"
+          label={spec.label}
+          value={text}
+          onChange={(_, { value }) => setText(value)}
+          onBlur={() => onChange(text)}
+        />
+      );
+  }
+};
+
+export const ParamsForm: React.FC<{
+  specs: ParamSpec[];
+  params: Record<string, ParamValue>;
+  onChange: (params: Record<string, ParamValue>) => void;
+}> = ({ specs, params, onChange }) => (
+  <Form size="mini">
+    {specs.map((spec) => (
+      <ParamInput
+        key={spec.name}
+        spec={spec}
+        value={params[spec.name] ?? spec.default}
+        onChange={(value) => onChange({ ...params, [spec.name]: value })}
+      />
+    ))}
+  </Form>
+);
@@ -1,9 +1,26 @@
+import { useState } from 'react';
 import type { NodeProps } from 'reactflow';
 
 import { Handle, Position } from 'reactflow';
 import { css } from '@acab/ecsstatic';
+import { Button, Form } from 'semantic-ui-react';
 
-import { InputPort as Input, OutputPort as Output } from './bindings';
+import {
+  Health,
+  ProcessState,
+  InputPort as Input,
+  OutputPort as Output,
+  ParamValue,
+  RestartPolicy,
+  setRestartPolicy,
+  updateParams,
+} from './bindings';
+import { useCandidate } from './candidates';
+import { useHealth } from './health';
+import { useMissingScopes } from './scopes';
+import { LogModal } from './LogModal';
+import { InjectModal } from './InjectModal';
+import { ParamsForm } from './ParamsForm';
 
 const LabelCSS = css`
   background: #fff;
@@ -28,10 +45,56 @@ const PortCSS = css`
   position: relative;
 `;
 
-const Label: React.FC<{ label: string }> = ({ label }) => (
-  <div className={LabelCSS}>{label}</div>
+const STATE_COLORS: Record<ProcessState, string> = {
+  initializing: '#2185d0',
+  running: '#21ba45',
+  restarting: '#f2711c',
+  failed: '#db2828',
+  stopped: '#767676',
+};
+
+const StateCSS = css`
+  float: right;
+  padding: 0 5px;
+  font-size: small;
+`;
+
+const Label: React.FC<{ label: string; health?: Health }> = ({
+  label,
+  health,
+}) => (
+  <div className={LabelCSS} title={health?.error ?? undefined}>
+    {label}
+    {health && (
+      <span className={StateCSS} style={{ color: STATE_COLORS[health.state] }}>
+        {health.state}
+        {health.restarts > 0 && ` (${health.restarts})`}
+      </span>
+    )}
+  </div>
 );
 
+const ErrorCSS = css`
+  background: #fff6f6;
+  color: #9f3a38;
+  grid-row: 4 / 5;
+  grid-column: 1 / 3;
+  padding: 5px;
+  font-size: small;
+  white-space: pre-wrap;
+  border-radius: 0 0 5px 5px;
+`;
+
+const ScopesCSS = css`
+  background: #fffaf3;
+  color: #573a08;
+  grid-row: 5 / 6;
+  grid-column: 1 / 3;
+  padding: 5px;
+  font-size: small;
+  border-radius: 0 0 5px 5px;
+`;
+
 const InputPort: React.FC<{
   input: Input;
 }> = ({ input }) => (
@@ -89,12 +152,38 @@ const OutputPorts: React.FC<{
   </div>
 );
 
+const ParamsCSS = css`
+  background: #fff;
+  grid-row: 3 / 4;
+  grid-column: 1 / 3;
+  padding: 5px;
+  border-radius: 0 0 5px 5px;
+`;
+
+const RESTART_POLICIES: { value: RestartPolicy; text: string }[] = [
+  { value: 'onFailure', text: 'Restart on failure' },
+  { value: 'always', text: 'Always restart' },
+  { value: 'never', text: 'Never restart' },
+];
+
+const RestartPolicySelect: React.FC<{
+  restart: RestartPolicy;
+  onChange: (restart: RestartPolicy) => void;
+}> = ({ restart, onChange }) => (
+  <Form.Select
+    className="nodrag"
+    value={restart}
+    options={RESTART_POLICIES.map((p) => ({ key: p.value, ...p }))}
+    onChange={(_, { value }) => onChange(value as RestartPolicy)}
+  />
+);
+
 const PropertiesNodeCSS = css`
   background: #000;
   border: 1px solid #000;
   min-width: 300px;
   display: grid;
-  grid-template-rows: 30px auto;
+  grid-template-rows: 30px auto auto auto auto;
   grid-template-columns: 1fr 1fr;
   gap: 1px;
   border-radius: 5px;
@@ -105,11 +194,68 @@ export const PropertiesNode: React.FC<
     label: string;
     inputs: Input[];
     outputs: Output[];
+    params: Record<string, ParamValue>;
+    restart?: RestartPolicy;
   }>
-> = ({ data: { label, inputs, outputs } }) => (
-  <div className={PropertiesNodeCSS}>
-    <Label label={label} />
-    <InputPorts inputs={inputs} />
-    <OutputPorts outputs={outputs} />
-  </div>
-);
+> = ({ id, type, data: { label, inputs, outputs, params, restart } }) => {
+  const specs = useCandidate(type)?.params ?? [];
+  const health = useHealth(id);
+  const missing = useMissingScopes(id);
+  const failing = health?.state === 'failed' || health?.state === 'restarting';
+  const [showLogs, setShowLogs] = useState(false);
+  const [showInject, setShowInject] = useState(false);
+
+  return (
+    <div
+      className={PropertiesNodeCSS}
+      style={health && { borderColor: STATE_COLORS[health.state] }}
+    >
+      <Label label={label} health={health} />
+      <InputPorts inputs={inputs} />
+      <OutputPorts outputs={outputs} />
+      <div className={ParamsCSS}>
+        {specs.length > 0 && (
+          <ParamsForm
+            specs={specs}
+            params={params ?? {}}
+            onChange={(updated) => updateParams(id, updated)}
+          />
+        )}
+        <Form size="mini">
+          <RestartPolicySelect
+            restart={restart ?? 'onFailure'}
+            onChange={(updated) => setRestartPolicy(id, updated)}
+          />
+        </Form>
+        <Button size="mini" onClick={() => setShowLogs(true)}>
+          Logs
+        </Button>
+        {outputs.length > 0 && (
+          <Button size="mini" onClick={() => setShowInject(true)}>
+            Inject
+          </Button>
+        )}
+        <LogModal
+          open={showLogs}
+          node={id}
+          label={label}
+          onClose={() => setShowLogs(false)}
+        />
+        <InjectModal
+          open={showInject}
+          node={id}
+          label={label}
+          onClose={() => setShowInject(false)}
+        />
+      </div>
+      {failing && health?.error && (
+        <div className={ErrorCSS}>{health.error}</div>
+      )}
+      {missing.length > 0 && (
+        <div className={ScopesCSS}>
+          Cannot run, the login lacks: {missing.join(', ')}
+        </div>
+      )}
+    </div>
+  );
+};
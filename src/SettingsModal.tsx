@@ -0,0 +1,86 @@
+import { useCallback, useEffect, useState } from 'react';
+
+import { Button, Form, Message, Modal } from 'semantic-ui-react';
+
+import {
+  Settings,
+  SettingsError,
+  settings as fetchSettings,
+  updateSettings,
+} from './bindings';
+
+const EMPTY: Settings = { channel: '', clientId: '', token: '' };
+
+const FIELDS: { name: keyof Settings; error: string; label: string }[] = [
+  { name: 'channel', error: 'channel', label: 'Channel' },
+  { name: 'clientId', error: 'client_id', label: 'Client ID' },
+  { name: 'token', error: 'token', label: 'Access token (instead of login)' },
+];
+
+type Prop = {
+  open: boolean;
+  onClose: () => void;
+};
+
+export const SettingsModal: React.FC<Prop> = ({ open, onClose }) => {
+  const [values, setValues] = useState<Settings>(EMPTY);
+  const [errors, setErrors] = useState<SettingsError[]>([]);
+
+  useEffect(() => {
+    if (!open) return;
+    setErrors([]);
+    (async () => {
+      setValues(await fetchSettings());
+    })();
+  }, [open]);
+
+  const onSave = useCallback(async () => {
+    try {
+      await updateSettings(values);
+      onClose();
+    } catch (errors) {
+      setErrors(errors as SettingsError[]);
+    }
+  }, [values, onClose]);
+
+  // errors which are not about a field, such as failing to save.
+  const others = errors.filter(
+    (e) => !FIELDS.some((f) => f.error === e.field),
+  );
+
+  return (
+    <Modal open={open} onClose={onClose} size="small">
+      <Modal.Header>Settings</Modal.Header>
+      <Modal.Content>
+        <Form error={errors.length > 0}>
+          {FIELDS.map((f) => {
+            const error = errors.find((e) => e.field === f.error);
+            return (
+              <Form.Input
+                key={f.name}
+                label={f.label}
+                type={f.name === 'token' ? 'password' : 'text'}
+                value={values[f.name]}
+                error={error && { content: error.message, pointing: 'above' }}
+                onChange={(_, { value }) =>
+                  setValues({ ...values, [f.name]: value })
+                }
+              />
+            );
+          })}
+          {others.length > 0 && (
+            <Message error list={others.map((e) => e.message)} />
+          )}
+        </Form>
+      </Modal.Content>
+      <Modal.Actions>
+        <Button onClick={onClose} secondary>
+          Cancel
+        </Button>
+        <Button onClick={onSave} primary>
+          Save
+        </Button>
+      </Modal.Actions>
+    </Modal>
+  );
+};
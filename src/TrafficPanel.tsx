@@ -0,0 +1,60 @@
+import { useCallback, useEffect, useState } from 'react';
+
+import { Checkbox, Header, Table } from 'semantic-ui-react';
+
+import { Traffic, setTap, traffic } from './bindings';
+
+// the backend only records, so the panel polls while it is shown.
+const INTERVAL_MS = 2000;
+
+export const TrafficPanel: React.FC<{ edge: string }> = ({ edge }) => {
+  const [state, setState] = useState<Traffic>({ enabled: false, packets: [] });
+
+  const reload = useCallback(async () => {
+    setState(await traffic(edge));
+  }, [edge]);
+
+  useEffect(() => {
+    reload();
+    const timer = setInterval(reload, INTERVAL_MS);
+    return () => clearInterval(timer);
+  }, [reload]);
+
+  const onToggle = useCallback(
+    async (enabled: boolean) => {
+      await setTap(edge, enabled);
+      await reload();
+    },
+    [edge, reload],
+  );
+
+  return (
+    <>
+      <Header as="h4">Recent traffic</Header>
+      <Checkbox
+        toggle
+        label="Record packets through this edge"
+        checked={state.enabled}
+        onChange={(_, { checked }) => onToggle(checked ?? false)}
+      />
+      {state.enabled && (
+        <Table compact size="small">
+          <Table.Body>
+            {[...state.packets].reverse().map((p, i) => (
+              <Table.Row key={i}>
+                <Table.Cell collapsing>
+                  {new Date(p.timestamp).toLocaleTimeString()}
+                </Table.Cell>
+                <Table.Cell>
+                  {Object.entries(p.message)
+                    .map(([k, v]) => `${k}: ${v}`)
+                    .join(', ')}
+                </Table.Cell>
+              </Table.Row>
+            ))}
+          </Table.Body>
+        </Table>
+      )}
+    </>
+  );
+};
@@ -0,0 +1,10 @@
+import { createContext, useContext } from 'react';
+
+import { Candidate } from './bindings';
+
+// candidates are fetched once by App, since they never change while running.
+export const CandidatesContext = createContext<Candidate[]>([]);
+
+export function useCandidate(kind: string): Candidate | undefined {
+  return useContext(CandidatesContext).find((c) => c.kind === kind);
+}
@@ -0,0 +1,10 @@
+import { createContext, useContext } from 'react';
+
+import { Health } from './bindings';
+
+// health of the running processes by the node id, kept up to date by usePipeline.
+export const HealthContext = createContext<Record<string, Health>>({});
+
+export function useHealth(id: string): Health | undefined {
+  return useContext(HealthContext)[id];
+}
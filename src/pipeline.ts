@@ -1,15 +1,26 @@
-import { useCallback, useMemo, useEffect, useRef, DependencyList } from 'react';
+import {
+  useCallback,
+  useMemo,
+  useEffect,
+  useRef,
+  useState,
+  DependencyList,
+} from 'react';
 import type { Edge as RFEdge, Connection, HandleType } from 'reactflow';
 import { useNodesState, useEdgesState, updateEdge } from 'reactflow';
 
 import { PropertiesNode } from './PropertiesNode';
 import {
   editor,
+  health as fetchHealth,
   updateEditor,
+  DroppedPacket,
+  Health,
   Node,
   Edge,
   InputPort,
   OutputPort,
+  ValidationIssue,
 } from './bindings';
 
 import { listen, EventName, EventCallback } from '@tauri-apps/api/event';
@@ -42,12 +53,24 @@ export function usePipeline({ onAssignEdit, onAddEdge, onRemoveEdge }: Args) {
   const nodeTypes = useMemo(
     () => ({
       TwitchSubscriber: PropertiesNode,
+      TwitchLookupUser: PropertiesNode,
+      TwitchLookupChannelInfo: PropertiesNode,
+      // deprecated, only to show pipelines saved before it was split.
       TwitchPublisher: PropertiesNode,
+      TwitchShoutout: PropertiesNode,
+      TwitchChat: PropertiesNode,
+      TwitchSendChatMessage: PropertiesNode,
+      TwitchAnnouncement: PropertiesNode,
+      Template: PropertiesNode,
     }),
     [],
   );
   const [nodes, setNodes, onNodesChange] = useNodesState([]);
   const [edges, setEdges, onEdgesChange] = useEdgesState([]);
+  const [health, setHealth] = useState<Record<string, Health>>({});
+  const [drops, setDrops] = useState<
+    Record<string, { property: string; count: number }>
+  >({});
 
   const rnodes: Node[] = useMemo(
     () => nodes.map((node) => ({ ...node, type: node.type ?? '' })),
@@ -96,11 +119,28 @@ export function usePipeline({ onAssignEdit, onAddEdge, onRemoveEdge }: Args) {
     [onAddEdge],
   );
 
+  // removed nodes disappear from the health only by fetching all again.
+  const loadHealth = useCallback(async () => {
+    const all = await fetchHealth();
+    setHealth(Object.fromEntries(all.map((h) => [h.node, h])));
+  }, []);
+
   const onApply = useCallback(() => {
     (async () => {
-      await updateEditor({ nodes: rnodes, edges: redges });
+      const show = (issues: ValidationIssue[]) =>
+        issues.map((i) => `${i.severity}: ${i.message}`).join('\n');
+      try {
+        const warnings = await updateEditor({ nodes: rnodes, edges: redges });
+        await loadHealth();
+        if (warnings.length > 0) {
+          alert(show(warnings));
+        }
+      } catch (issues) {
+        const details = show(issues as ValidationIssue[]);
+        alert(`The pipeline was not deployed.\n${details}`);
+      }
     })();
-  }, [rnodes, redges]);
+  }, [rnodes, redges, loadHealth]);
 
   const onEdgeClick = useCallback(
     (_e: React.MouseEvent, edge: RFEdge) => {
@@ -131,8 +171,9 @@ export function usePipeline({ onAssignEdit, onAddEdge, onRemoveEdge }: Args) {
       const { nodes, edges } = await editor();
       setNodes(nodes);
       setEdges(edges);
+      await loadHealth();
     })();
-  }, [setNodes, setEdges]);
+  }, [setNodes, setEdges, loadHealth]);
 
   useListen(
     'pipeline-updated',
@@ -146,10 +187,44 @@ export function usePipeline({ onAssignEdit, onAddEdge, onRemoveEdge }: Args) {
     [setNodes, setEdges],
   );
 
+  useListen<Health>(
+    'health-updated',
+    ({ payload }) => {
+      setHealth((health) => ({ ...health, [payload.node]: payload }));
+    },
+    [setHealth],
+  );
+
+  useListen<DroppedPacket>(
+    'packet-dropped',
+    ({ payload }) => {
+      // what the nodes discard is shown in the log panel instead.
+      if (payload.kind !== 'missing') return;
+      const { edge, property } = payload;
+      setDrops((drops) => ({
+        ...drops,
+        [edge]: { property, count: (drops[edge]?.count ?? 0) + 1 },
+      }));
+    },
+    [setDrops],
+  );
+
+  // show how many messages each edge could not deliver as is.
+  const labeled = useMemo(
+    () =>
+      edges.map((edge) => {
+        const drop = drops[edge.id];
+        if (!drop) return edge;
+        return { ...edge, label: `missing ${drop.property} (${drop.count})` };
+      }),
+    [edges, drops],
+  );
+
   return {
     nodes,
-    edges,
+    edges: labeled,
     nodeTypes,
+    health,
     onNodesChange,
     onEdgesChange,
     onEdgeUpdateStart,
@@ -0,0 +1,37 @@
+import { createContext, useContext, useEffect, useState } from 'react';
+import { listen } from '@tauri-apps/api/event';
+
+import { MissingScopes, missingScopes } from './bindings';
+
+// scopes which the token lacks by the node id. nodes which can run are not included.
+export const ScopesContext = createContext<Record<string, string[]>>({});
+
+export function useMissingScopes(id: string): string[] {
+  return useContext(ScopesContext)[id] ?? [];
+}
+
+const byNode = (all: MissingScopes[]) =>
+  Object.fromEntries(all.map((m) => [m.component, m.scopes]));
+
+// keeps the missing scopes up to date, as they are checked after every deployment.
+export function useScopes(): Record<string, string[]> {
+  const [scopes, setScopes] = useState<Record<string, string[]>>({});
+  useEffect(() => {
+    let unlisten: () => void = () => ({});
+    (async () => {
+      unlisten = await listen<MissingScopes[]>(
+        'scopes-checked',
+        ({ payload }) => setScopes(byNode(payload)),
+      );
+      try {
+        setScopes(byNode(await missingScopes()));
+      } catch {
+        // not logged in yet.
+      }
+    })();
+    return () => {
+      unlisten();
+    };
+  }, []);
+  return scopes;
+}